@@ -1,11 +1,13 @@
 use askama::Template;
+use heck::{ToShoutySnakeCase, ToSnakeCase};
 use quote::ToTokens;
 use std::path::Path;
 use syn::{Expr, ExprUnary, Type, TypePath, UnOp};
 
 use super::post_process_shared::{
-    DefaultParamValue, ParsedMethod, ParsedModel, ParsedReturnType, parse_lera_models,
-    to_camel_case, type_path_generic_args,
+    DefaultParamValue, ParsedActions, ParsedChild, ParsedMethod, ParsedModel, ParsedNavigation,
+    ParsedReturnType, parse_lera_models, parse_serde_states, to_camel_case,
+    to_computed_state_fn_name, type_path_generic_args,
 };
 
 #[derive(Debug, Clone)]
@@ -15,21 +17,115 @@ struct KotlinMethodParam {
     default_value: Option<String>,
 }
 
+#[derive(Debug, Clone)]
+struct KotlinStateField {
+    name: String,
+    /// Whether the field is an `Option`, always set by a `{State}Diff`.
+    is_optional: bool,
+}
+
 #[derive(Debug, Clone)]
 struct KotlinModelInfo {
     model_name: String,
     state_name: String,
     listener_name: String,
+    change_name: String,
+    /// The name of the listener parameter receiving the change.
+    change_param: String,
+    /// The state, or its diff, in the forwarded `change`.
+    state_of_change: String,
     default_state_fn: String,
     samples_state_fn: String,
     enable_samples: bool,
+    enable_diff: bool,
+    enable_history: bool,
+    enable_recorder: bool,
+    enable_notify_policy: bool,
+    state_fields: Vec<KotlinStateField>,
+    /// Whether the state has `#[lera::computed]` properties, delivered with every
+    /// change and initially evaluated by `computed_fn`.
+    has_computed: bool,
+    computed_fn: String,
+    /// The `#[lera::child]` models, exposed as child ViewModels.
+    children: Vec<ParsedChild>,
+    /// Whether another model has this one as a child, sharing its instance.
+    is_child: bool,
+    /// The `navigation = field` stack, bound to a `NavController`.
+    navigation: Option<KotlinNavigation>,
+    /// The `actions = MyAction` of the model, sent with `send(action)`.
+    actions: Option<KotlinActions>,
+    /// The `events = MyEvent` of the model, exposed as a `SharedFlow`.
+    events: Option<String>,
     methods: Vec<String>,
 }
 
+#[derive(Debug, Clone)]
+struct KotlinNavigation {
+    field: String,
+    destination_name: String,
+}
+
+#[derive(Debug, Clone)]
+struct KotlinActions {
+    name: String,
+    /// A method per action, unless it has unnamed fields or its name is taken.
+    methods: Vec<KotlinActionMethod>,
+}
+
+/// A method sending one action, `fun setCount(count: Int)`.
+#[derive(Debug, Clone)]
+struct KotlinActionMethod {
+    name: String,
+    params: Vec<KotlinMethodParam>,
+    /// The action sent, `CounterAction.SetCount(count)`.
+    action: String,
+}
+
+/// The `NavHost` routes of a `#[lera::navigation]` enum.
+#[derive(Debug, Clone)]
+struct KotlinDestinationRoutes {
+    name: String,
+    routes: Vec<KotlinRoute>,
+}
+
+#[derive(Debug, Clone)]
+struct KotlinRoute {
+    /// The constant holding the route, `PROFILE`.
+    constant: String,
+    route: String,
+    /// The `when` branch matching the destination, `is Destination.Profile`.
+    pattern: String,
+}
+
+/// A `#[lera::state(serde)]` state, serialized through a `@Serializable`
+/// surrogate with the same JSON keys.
+#[derive(Debug, Clone)]
+struct KotlinSerializableState {
+    name: String,
+    fields: Vec<KotlinSerialField>,
+}
+
+#[derive(Debug, Clone)]
+struct KotlinSerialField {
+    name: String,
+    wire_name: String,
+    kotlin_type: String,
+    /// The serializer of a field holding another serializable state.
+    serializer: Option<String>,
+}
+
 #[derive(Template)]
 #[template(path = "view_model.kt.jinja", escape = "none")]
 struct KotlinViewModelTemplate {
     models: Vec<KotlinModelInfo>,
+    serializable_states: Vec<KotlinSerializableState>,
+    destination_routes: Vec<KotlinDestinationRoutes>,
+    /// Whether the crate declared `lera_setup_ffi_for_executor!()`.
+    uses_foreign_executor: bool,
+    /// Whether the crate declared `lera_setup_ffi_for_ui_dispatcher!()`.
+    uses_ui_dispatcher: bool,
+    /// Whether the crate declared `lera_setup_ffi_for_persistence!()`.
+    uses_persistence: bool,
 }
 
 struct ReturnMetadata {
@@ -47,7 +143,7 @@ pub(crate) fn kotlin_transform(
 
     let models: Vec<KotlinModelInfo> = parsed_models
         .iter()
-        .map(build_model_info)
+        .map(|model| build_model_info(model, &parsed_models))
         .collect::<Result<_, _>>()?;
 
     for model in &models {
@@ -58,17 +154,75 @@ pub(crate) fn kotlin_transform(
         );
     }
 
-    let template = KotlinViewModelTemplate { models };
+    let serde_states = parse_serde_states(path_to_target_rust_crate)?;
+    let serializable_states = serde_states
+        .iter()
+        .map(|state| KotlinSerializableState {
+            name: state.name.clone(),
+            fields: state
+                .fields
+                .iter()
+                .map(|field| {
+                    let kotlin_type = kotlin_type_from_syn_type(&field.ty);
+                    let serializer = serde_states
+                        .iter()
+                        .find(|other| other.name == kotlin_type.trim_end_matches('?'))
+                        .map(|other| format!("{}Serializer", other.name));
+                    KotlinSerialField {
+                        name: field.camel_name.clone(),
+                        wire_name: field.rust_name.clone(),
+                        kotlin_type,
+                        serializer,
+                    }
+                })
+                .collect(),
+        })
+        .collect();
+
+    let mut destination_routes: Vec<KotlinDestinationRoutes> = Vec::new();
+    for navigation in parsed_models
+        .iter()
+        .filter_map(|model| model.navigation.as_ref())
+    {
+        if destination_routes
+            .iter()
+            .all(|routes| routes.name != navigation.destination_name)
+        {
+            destination_routes.push(build_destination_routes(navigation));
+        }
+    }
+
+    let uses_persistence = corpus.contains("fun installStateStorage(");
+    let uses_events = models.iter().any(|model| model.events.is_some());
+    let template = KotlinViewModelTemplate {
+        models,
+        serializable_states,
+        destination_routes,
+        uses_foreign_executor: corpus.contains("fun installForeignExecutor("),
+        uses_ui_dispatcher: corpus.contains("fun installUiDispatcher("),
+        uses_persistence,
+    };
     let generated_kotlin = template
         .render()
         .map_err(|e| format!("Template rendering failed: {}", e))?;
 
     let mut result = corpus;
     if !result.contains("kotlinx.coroutines.flow.StateFlow") {
-        let imports = "import androidx.lifecycle.ViewModel\n".to_string()
+        let mut imports = "import androidx.lifecycle.ViewModel\n".to_string()
             + "import kotlinx.coroutines.flow.MutableStateFlow\n"
             + "import kotlinx.coroutines.flow.StateFlow\n"
-            + "import kotlinx.coroutines.flow.asStateFlow\n";
+            + "import kotlinx.coroutines.flow.asStateFlow\n"
+            + "import kotlinx.coroutines.launch\n";
+        if uses_persistence {
+            imports += "import androidx.datastore.preferences.core.edit\n";
+            imports += "import kotlinx.coroutines.flow.first\n";
+        }
+        if uses_events {
+            imports += "import androidx.lifecycle.viewModelScope\n";
+            imports += "import kotlinx.coroutines.channels.awaitClose\n";
+            imports += "import kotlinx.coroutines.flow.buffer\n";
+            imports += "import kotlinx.coroutines.flow.shareIn\n";
+        }
 
         if let Some(pkg_pos) = result.find("\npackage ") {
             let pkg_line_start = pkg_pos + 1;
@@ -86,7 +240,10 @@ pub(crate) fn kotlin_transform(
     Ok(result)
 }
 
-fn build_model_info(model: &ParsedModel) -> Result<KotlinModelInfo, String> {
+fn build_model_info(
+    model: &ParsedModel,
+    models: &[ParsedModel],
+) -> Result<KotlinModelInfo, String> {
     let methods = model
         .methods
         .iter()
@@ -97,13 +254,119 @@ fn build_model_info(model: &ParsedModel) -> Result<KotlinModelInfo, String> {
         model_name: model.model_name.clone(),
         state_name: model.state_name.clone(),
         listener_name: model.listener_name.clone(),
+        change_name: model.change_name(),
+        change_param: model.change_param().to_owned(),
+        state_of_change: model.state_of_change(),
         default_state_fn: model.default_state_fn.clone(),
         samples_state_fn: model.samples_state_fn.clone(),
         enable_samples: model.enable_samples,
+        enable_diff: model.enable_diff,
+        enable_history: model.enable_history,
+        enable_recorder: model.enable_recorder,
+        enable_notify_policy: model.enable_notify_policy,
+        state_fields: model
+            .state_fields
+            .iter()
+            .map(|field| KotlinStateField {
+                name: field.camel_name.clone(),
+                is_optional: field.is_optional(),
+            })
+            .collect(),
+        has_computed: !model.computed_fields.is_empty(),
+        computed_fn: to_computed_state_fn_name(&model.state_name),
+        children: model.children.clone(),
+        is_child: model.is_child_of_any(models),
+        navigation: model
+            .navigation
+            .as_ref()
+            .map(|navigation| KotlinNavigation {
+                field: navigation.field_camel_name.clone(),
+                destination_name: navigation.destination_name.clone(),
+            }),
+        actions: model
+            .actions
+            .as_ref()
+            .map(|actions| build_actions(actions, model)),
+        events: model.events.clone(),
         methods,
     })
 }
 
+fn build_actions(actions: &ParsedActions, model: &ParsedModel) -> KotlinActions {
+    let name = &actions.name;
+    // UniFFI generates an `enum class` when no variant has fields, a `sealed class` otherwise.
+    let is_enum_class = actions
+        .actions
+        .iter()
+        .all(|action| action.fields.is_empty() && !action.is_tuple);
+    let methods = actions
+        .actions
+        .iter()
+        .filter(|action| !action.is_tuple)
+        .map(|action| {
+            let params: Vec<KotlinMethodParam> = action
+                .fields
+                .iter()
+                .map(|field| KotlinMethodParam {
+                    name: field.camel_name.clone(),
+                    param_type: kotlin_type_from_syn_type(&field.ty),
+                    default_value: None,
+                })
+                .collect();
+            let action_value = if is_enum_class {
+                format!("{}.{}", name, action.name.to_shouty_snake_case())
+            } else if params.is_empty() {
+                format!("{}.{}", name, action.name)
+            } else {
+                let arguments = params
+                    .iter()
+                    .map(|param| param.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{}.{}({})", name, action.name, arguments)
+            };
+            KotlinActionMethod {
+                name: to_camel_case(&action.name.to_snake_case()),
+                params,
+                action: action_value,
+            }
+        })
+        .filter(|method| {
+            method.name != "send" && model.methods.iter().all(|m| m.camel_name != method.name)
+        })
+        .collect();
+    KotlinActions {
+        name: name.clone(),
+        methods,
+    }
+}
+
+fn build_destination_routes(navigation: &ParsedNavigation) -> KotlinDestinationRoutes {
+    let name = &navigation.destination_name;
+    // UniFFI generates an `enum class` when no variant has fields, a `sealed class` otherwise.
+    let is_enum_class = navigation
+        .destinations
+        .iter()
+        .all(|destination| !destination.has_fields);
+    let routes = navigation
+        .destinations
+        .iter()
+        .map(|destination| KotlinRoute {
+            constant: destination.name.to_shouty_snake_case(),
+            route: destination.name.to_snake_case(),
+            pattern: if is_enum_class {
+                format!("{}.{}", name, destination.name.to_shouty_snake_case())
+            } else {
+                format!("is {}.{}", name, destination.name)
+            },
+        })
+        .collect();
+    KotlinDestinationRoutes {
+        name: name.clone(),
+        routes,
+    }
+}
+
 fn build_method(method: &ParsedMethod, model: &ParsedModel) -> Result<String, String> {
     let parameters = method_params_to_kotlin(method, model)?;
     let camel_params: Vec<String> = parameters
@@ -157,6 +420,24 @@ fn build_method(method: &ParsedMethod, model: &ParsedModel) -> Result<String, St
         format!("(\n{}\n        )", call_args.join("\n"))
     };
 
+    if method.return_type.is_stream {
+        // Every collection iterates a new stream, cancelled once it stops.
+        let item_type = return_metadata.kotlin_type.as_deref().unwrap_or("Unit");
+        return Ok(format!(
+            "fun {}{}: kotlinx.coroutines.flow.Flow<{}> = kotlinx.coroutines.flow.flow {{\n\
+             \x20       val stream = model.{}{}\n\
+             \x20       try {{\n\
+             \x20           while (true) {{\n\
+             \x20               emit(stream.next() ?: break)\n\
+             \x20           }}\n\
+             \x20       }} finally {{\n\
+             \x20           stream.cancel()\n\
+             \x20       }}\n\
+             \x20   }}",
+            method.camel_name, param_part, item_type, method.camel_name, call_params
+        ));
+    }
+
     let return_part = return_metadata
         .kotlin_type
         .as_ref()
@@ -379,10 +660,11 @@ fn default_expr_to_kotlin(expr: &Expr, kotlin_type: &str) -> Option<String> {
         }),
         Expr::Path(expr_path) => {
             let mut segments = expr_path.path.segments.iter();
-            if let Some(first) = segments.next() {
-                if first.ident == "None" && segments.next().is_none() {
-                    return Some("null".to_string());
-                }
+            if let Some(first) = segments.next()
+                && first.ident == "None"
+                && segments.next().is_none()
+            {
+                return Some("null".to_string());
             }
             None
         }
@@ -5,10 +5,11 @@ use std::{
 };
 
 use syn::{
-    Attribute, Expr, FnArg, Item, ItemImpl, ItemStruct, Pat, ReturnType, Type, TypePath,
+    Attribute, Expr, FnArg, Item, ItemEnum, ItemImpl, ItemStruct, Pat, ReturnType, Type, TypePath,
     Visibility,
     parse::{Parse, ParseStream},
     parse_file,
+    punctuated::Punctuated,
 };
 
 /// Represents the default value declared via `#[lera::default_params]`.
@@ -33,6 +34,9 @@ pub struct ParsedParam {
 pub struct ParsedReturnType {
     pub ty: Option<Type>,
     pub uses_result: bool,
+    /// Whether the method returns a stream, `ty` and `uses_result` then
+    /// describing its items.
+    pub is_stream: bool,
 }
 
 /// Method metadata for a model annotated with `#[lera::api]`.
@@ -45,6 +49,63 @@ pub struct ParsedMethod {
     pub is_async: bool,
 }
 
+/// A field of the state struct of a model.
+#[derive(Debug, Clone)]
+pub struct ParsedStateField {
+    /// The Rust field name, which is also its name in the `serde` wire format.
+    pub rust_name: String,
+    pub camel_name: String,
+    pub ty: Type,
+}
+
+impl ParsedStateField {
+    /// Whether the field is an `Option`, held as is by the `{State}Diff` of a
+    /// `#[lera::state(diff)]` instead of wrapped in another `Option`.
+    pub fn is_optional(&self) -> bool {
+        matches!(&self.ty, Type::Path(type_path)
+            if type_path.path.segments.last().is_some_and(|segment| segment.ident == "Option"))
+    }
+}
+
+/// A `#[lera::child]` field of a model, `name: Arc<Model>`.
+#[derive(Debug, Clone)]
+pub struct ParsedChild {
+    pub camel_name: String,
+    pub model_name: String,
+}
+
+/// The `navigation = field` of a model, a stack of `#[lera::navigation]` destinations.
+#[derive(Debug, Clone)]
+pub struct ParsedNavigation {
+    pub field_camel_name: String,
+    pub destination_name: String,
+    pub destinations: Vec<ParsedDestination>,
+}
+
+/// A variant of a `#[lera::navigation]` enum.
+#[derive(Debug, Clone)]
+pub struct ParsedDestination {
+    pub name: String,
+    pub has_fields: bool,
+}
+
+/// The `actions = MyAction` of a model, a `#[lera::actions]` enum.
+#[derive(Debug, Clone)]
+pub struct ParsedActions {
+    pub name: String,
+    pub actions: Vec<ParsedAction>,
+}
+
+/// A variant of a `#[lera::actions]` enum.
+#[derive(Debug, Clone)]
+pub struct ParsedAction {
+    pub name: String,
+    /// The named fields of the variant, empty for a unit variant.
+    pub fields: Vec<ParsedStateField>,
+    /// Whether the variant has unnamed fields, which get no convenience method.
+    pub is_tuple: bool,
+}
+
 /// Parsed representation of a `#[lera::model]` implementation.
 #[derive(Debug, Clone)]
 pub struct ParsedModel {
@@ -54,10 +115,66 @@ pub struct ParsedModel {
     pub default_state_fn: String,
     pub samples_state_fn: String,
     pub enable_samples: bool,
+    pub enable_diff: bool,
+    /// Whether the model keeps an undo/redo history, `history = N`.
+    pub enable_history: bool,
+    /// Whether the model can record its state changes, `recorder`.
+    pub enable_recorder: bool,
+    /// Whether notifications are delayed by `notify = ...`, exporting `flush`.
+    pub enable_notify_policy: bool,
+    pub state_fields: Vec<ParsedStateField>,
+    /// The methods of the `#[lera::computed]` impl block of the state.
+    pub computed_fields: Vec<ParsedStateField>,
+    /// The `#[lera::child]` fields of the model.
+    pub children: Vec<ParsedChild>,
+    pub navigation: Option<ParsedNavigation>,
+    /// The `actions = MyAction` the model reduces, exporting `send_action`.
+    pub actions: Option<ParsedActions>,
+    /// The `events = MyEvent` the model emits, exporting `subscribe_events`.
+    pub events: Option<String>,
+    pub traits: ModelTraits,
     pub methods: Vec<ParsedMethod>,
     pub source_path: PathBuf,
 }
 
+impl ParsedModel {
+    /// Whether any of `models` has this model as a `#[lera::child]`.
+    pub fn is_child_of_any(&self, models: &[ParsedModel]) -> bool {
+        models.iter().any(|model| {
+            model
+                .children
+                .iter()
+                .any(|child| child.model_name == self.model_name)
+        })
+    }
+
+    /// Name of the type listeners receive on state change, with computed
+    /// properties the `{State}Change` holding them and the state or its diff.
+    pub fn change_name(&self) -> String {
+        if !self.computed_fields.is_empty() {
+            format!("{}Change", self.state_name)
+        } else if self.enable_diff {
+            format!("{}Diff", self.state_name)
+        } else {
+            self.state_name.clone()
+        }
+    }
+
+    /// Name of the listener parameter receiving the change.
+    pub fn change_param(&self) -> &'static str {
+        if self.enable_diff { "diff" } else { "state" }
+    }
+
+    /// The state, or its diff, in the change named `change`.
+    pub fn state_of_change(&self) -> String {
+        match (self.computed_fields.is_empty(), self.enable_diff) {
+            (true, _) => "change".to_owned(),
+            (false, true) => "change.diff".to_owned(),
+            (false, false) => "change.state".to_owned(),
+        }
+    }
+}
+
 pub fn to_camel_case(snake_case: &str) -> String {
     let mut result = String::new();
     let mut capitalize_next = false;
@@ -82,6 +199,13 @@ pub fn to_default_state_fn_name(state_name: &str) -> String {
     result
 }
 
+/// The exported function generated by `#[lera::computed]`, e.g. `counterStateComputed`.
+pub fn to_computed_state_fn_name(state_name: &str) -> String {
+    let mut chars = state_name.chars();
+    let first = chars.next().map(|ch| ch.to_lowercase().collect::<String>());
+    format!("{}{}Computed", first.unwrap_or_default(), chars.as_str())
+}
+
 pub fn to_samples_state_fn_name(state_name: &str) -> String {
     let mut result = "new".to_string();
     result.push_str(state_name);
@@ -91,12 +215,11 @@ pub fn to_samples_state_fn_name(state_name: &str) -> String {
 
 fn attr_is_lera(attr: &Attribute, name: &str) -> bool {
     let mut segments = attr.path().segments.iter();
-    if let Some(first) = segments.next() {
-        if first.ident == "lera" {
-            if let Some(second) = segments.next() {
-                return second.ident == name && segments.next().is_none();
-            }
-        }
+    if let Some(first) = segments.next()
+        && first.ident == "lera"
+        && let Some(second) = segments.next()
+    {
+        return second.ident == name && segments.next().is_none();
     }
     false
 }
@@ -147,6 +270,11 @@ fn is_uniffi_constructor(attrs: &[Attribute]) -> bool {
     })
 }
 
+/// Lifecycle hooks are moved out of the exported impl block by `#[lera::api]`.
+fn is_lifecycle_hook(attrs: &[Attribute]) -> bool {
+    has_lera_attr(attrs, "on_init") || has_lera_attr(attrs, "on_drop")
+}
+
 pub fn parse_lera_models(path_to_target_rust_crate: &Path) -> Result<Vec<ParsedModel>, String> {
     let manifest_dir = path_to_target_rust_crate;
     let search_dirs = [manifest_dir.join("src")];
@@ -163,6 +291,50 @@ pub fn parse_lera_models(path_to_target_rust_crate: &Path) -> Result<Vec<ParsedM
         models.append(&mut dir_models);
     }
 
+    if models.iter().any(|model| model.navigation.is_some()) {
+        let enums = parse_lera_enums(&manifest_dir.join("src"), "navigation")?;
+        for navigation in models
+            .iter_mut()
+            .filter_map(|model| model.navigation.as_mut())
+        {
+            let item_enum = enums.get(&navigation.destination_name).ok_or_else(|| {
+                format!(
+                    "ACTIONABLE ERROR: #[lera::navigation] enum {} not found",
+                    navigation.destination_name
+                )
+            })?;
+            navigation.destinations = item_enum
+                .variants
+                .iter()
+                .map(|variant| ParsedDestination {
+                    name: variant.ident.to_string(),
+                    has_fields: !variant.fields.is_empty(),
+                })
+                .collect();
+        }
+    }
+
+    if models.iter().any(|model| model.actions.is_some()) {
+        let enums = parse_lera_enums(&manifest_dir.join("src"), "actions")?;
+        for actions in models.iter_mut().filter_map(|model| model.actions.as_mut()) {
+            let item_enum = enums.get(&actions.name).ok_or_else(|| {
+                format!(
+                    "ACTIONABLE ERROR: #[lera::actions] enum {} not found",
+                    actions.name
+                )
+            })?;
+            actions.actions = item_enum
+                .variants
+                .iter()
+                .map(|variant| ParsedAction {
+                    name: variant.ident.to_string(),
+                    fields: parse_state_fields(&variant.fields),
+                    is_tuple: matches!(variant.fields, syn::Fields::Unnamed(_)),
+                })
+                .collect();
+        }
+    }
+
     if models.is_empty() {
         let formatted_dirs = inspected_dirs
             .into_iter()
@@ -178,11 +350,40 @@ pub fn parse_lera_models(path_to_target_rust_crate: &Path) -> Result<Vec<ParsedM
     Ok(models)
 }
 
+/// Every enum marked with `#[lera::{name}]`, by enum name.
+fn parse_lera_enums(dir: &Path, name: &str) -> Result<HashMap<String, ItemEnum>, String> {
+    let mut enums = HashMap::new();
+    if !dir.exists() {
+        return Ok(enums);
+    }
+    for (_, syntax_tree) in parse_rust_files_in_dir(dir)? {
+        for item in syntax_tree.items {
+            if let Item::Enum(item_enum) = item
+                && has_lera_attr(&item_enum.attrs, name)
+            {
+                enums.insert(item_enum.ident.to_string(), item_enum);
+            }
+        }
+    }
+    Ok(enums)
+}
+
 fn parse_models_in_dir(dir: &Path) -> Result<Vec<ParsedModel>, String> {
+    let mut models = Vec::new();
+
+    for (path, syntax_tree) in parse_rust_files_in_dir(dir)? {
+        let file_models = parse_file_for_lera_models(&syntax_tree, &path)?;
+        models.extend(file_models);
+    }
+
+    Ok(models)
+}
+
+fn parse_rust_files_in_dir(dir: &Path) -> Result<Vec<(PathBuf, syn::File)>, String> {
     let entries = fs::read_dir(dir)
         .map_err(|e| format!("Failed to read models directory {}: {}", dir.display(), e))?;
 
-    let mut models = Vec::new();
+    let mut files = Vec::new();
 
     for entry in entries {
         let entry = entry
@@ -196,12 +397,102 @@ fn parse_models_in_dir(dir: &Path) -> Result<Vec<ParsedModel>, String> {
             let syntax_tree =
                 parse_file(&content).map_err(|e| format!("Failed to parse {:?}: {}", path, e))?;
 
-            let file_models = parse_file_for_lera_models(&syntax_tree, &path)?;
-            models.extend(file_models);
+            files.push((path, syntax_tree));
         }
     }
 
-    Ok(models)
+    Ok(files)
+}
+
+/// A state declared with `#[lera::state(serde)]`, for which the foreign types
+/// get the same JSON representation.
+#[derive(Debug, Clone)]
+pub struct ParsedSerdeState {
+    pub name: String,
+    pub fields: Vec<ParsedStateField>,
+}
+
+/// Finds all `#[lera::state(serde)]` structs, not only those of models, so that
+/// states nested in other states are serializable too.
+pub fn parse_serde_states(
+    path_to_target_rust_crate: &Path,
+) -> Result<Vec<ParsedSerdeState>, String> {
+    let dir = path_to_target_rust_crate.join("src");
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut states = Vec::new();
+    for (path, syntax_tree) in parse_rust_files_in_dir(&dir)? {
+        for item in &syntax_tree.items {
+            let Item::Struct(ItemStruct {
+                ident,
+                attrs,
+                fields,
+                ..
+            }) = item
+            else {
+                continue;
+            };
+            let Some(attr) = attrs.iter().find(|a| attr_is_lera(a, "state")) else {
+                continue;
+            };
+            if matches!(attr.meta, syn::Meta::Path(_)) {
+                continue;
+            }
+            let args = attr.parse_args::<StateAttrArgs>().map_err(|e| {
+                format!(
+                    "Failed to parse #[lera::state] attribute on {} in {:?}: {}",
+                    ident, path, e
+                )
+            })?;
+            if args.serde {
+                states.push(ParsedSerdeState {
+                    name: ident.to_string(),
+                    fields: parse_state_fields(fields),
+                });
+            }
+        }
+    }
+
+    Ok(states)
+}
+
+fn parse_state_fields(fields: &syn::Fields) -> Vec<ParsedStateField> {
+    fields
+        .iter()
+        .filter_map(|field| {
+            field.ident.as_ref().map(|ident| ParsedStateField {
+                rust_name: ident.to_string(),
+                camel_name: to_camel_case(&ident.to_string()),
+                ty: field.ty.clone(),
+            })
+        })
+        .collect()
+}
+
+fn parse_child_fields(fields: &syn::Fields) -> Vec<ParsedChild> {
+    fields
+        .iter()
+        .filter(|field| has_lera_attr(&field.attrs, "child"))
+        .filter_map(|field| {
+            let ident = field.ident.as_ref()?;
+            let Type::Path(TypePath { path, .. }) = &field.ty else {
+                return None;
+            };
+            // `Arc<Model>`
+            let arc = path.segments.last()?;
+            let Some(Type::Path(TypePath { path: model, .. })) =
+                type_path_generic_args(arc).first().copied()
+            else {
+                return None;
+            };
+            Some(ParsedChild {
+                camel_name: to_camel_case(&ident.to_string()),
+                model_name: model.segments.last()?.ident.to_string(),
+            })
+        })
+        .collect()
 }
 
 fn parse_file_for_lera_models(
@@ -211,37 +502,82 @@ fn parse_file_for_lera_models(
     let mut models = Vec::new();
 
     for item in &syntax_tree.items {
-        if let Item::Struct(ItemStruct { ident, attrs, .. }) = item {
-            if has_lera_attr(attrs, "model") {
-                let state_name = attrs
-                    .iter()
-                    .find(|attr| attr_is_lera(attr, "model"))
-                    .map(|attr| {
-                        attr.parse_args::<ModelAttrArgs>().map_err(|e| {
-                            format!(
-                                "Failed to parse #[lera::model] attribute on {} in {:?}: {}",
-                                ident, file_path, e
-                            )
-                        })
-                    })
-                    .transpose()? // Option<Result<...>> -> Result<Option<...>>
-                    .map(|args| type_to_string(&args.state_ty))
-                    .ok_or_else(|| {
+        if let Item::Struct(ItemStruct { ident, attrs, .. }) = item
+            && has_lera_attr(attrs, "model")
+        {
+            let args = attrs
+                .iter()
+                .find(|attr| attr_is_lera(attr, "model"))
+                .map(|attr| {
+                    attr.parse_args::<ModelAttrArgs>().map_err(|e| {
                         format!(
-                            "#[lera::model] attribute on {} in {:?} must specify a state",
-                            ident, file_path
+                            "Failed to parse #[lera::model] attribute on {} in {:?}: {}",
+                            ident, file_path, e
                         )
-                    })?;
+                    })
+                })
+                .transpose()? // Option<Result<...>> -> Result<Option<...>>
+                .ok_or_else(|| {
+                    format!(
+                        "#[lera::model] attribute on {} in {:?} must specify a state",
+                        ident, file_path
+                    )
+                })?;
+            let state_name = type_to_string(&args.state_ty);
 
-                let model_info = collect_model_info(ident, &state_name, syntax_tree, file_path)?;
-                models.push(model_info);
-            }
+            let mut model_info = collect_model_info(ident, &state_name, syntax_tree, file_path)?;
+            model_info.traits = args.traits;
+            model_info.enable_history = args.history;
+            model_info.enable_recorder = args.recorder;
+            model_info.enable_notify_policy = args.notify;
+            model_info.navigation = args
+                .navigation
+                .map(|field| parse_navigation_field(&model_info, &field, file_path))
+                .transpose()?;
+            model_info.actions = args.actions.map(|actions| ParsedActions {
+                name: type_to_string(&actions),
+                actions: Vec::new(),
+            });
+            model_info.events = args.events.as_ref().map(type_to_string);
+            models.push(model_info);
         }
     }
 
     Ok(models)
 }
 
+/// The `navigation = field` of `model`, whose type must be a `{Destination}Stack`.
+fn parse_navigation_field(
+    model: &ParsedModel,
+    field: &syn::Ident,
+    file_path: &Path,
+) -> Result<ParsedNavigation, String> {
+    let state_field = model
+        .state_fields
+        .iter()
+        .find(|state_field| *field == state_field.rust_name)
+        .ok_or_else(|| {
+            format!(
+                "ACTIONABLE ERROR: navigation field {} not found in {} in {:?}",
+                field, model.state_name, file_path
+            )
+        })?;
+    let destination_name = type_to_string(&state_field.ty)
+        .strip_suffix("Stack")
+        .map(str::to_owned)
+        .ok_or_else(|| {
+            format!(
+                "ACTIONABLE ERROR: navigation field {} of {} must be the `Stack` of a #[lera::navigation] enum in {:?}",
+                field, model.state_name, file_path
+            )
+        })?;
+    Ok(ParsedNavigation {
+        field_camel_name: state_field.camel_name.clone(),
+        destination_name,
+        destinations: Vec::new(),
+    })
+}
+
 fn collect_model_info(
     model_ident: &syn::Ident,
     state_name: &str,
@@ -252,12 +588,20 @@ fn collect_model_info(
     let listener_name = format!("{}ChangeListener", state_name);
     let mut found_state_struct = false;
     let mut found_api_impl = false;
-    let mut enable_samples = false;
+    let mut state_args = StateAttrArgs::default();
+    let mut state_fields = Vec::new();
+    let mut computed_fields = Vec::new();
+    let mut children = Vec::new();
     let mut methods = Vec::new();
 
     for item in &syntax_tree.items {
         match item {
-            Item::Struct(ItemStruct { ident, attrs, .. }) => {
+            Item::Struct(ItemStruct {
+                ident,
+                attrs,
+                fields,
+                ..
+            }) => {
                 if ident == model_ident && !has_lera_attr(attrs, "model") {
                     return Err(format!(
                         "ACTIONABLE ERROR: struct {} must use #[lera::model] in {:?}",
@@ -265,6 +609,10 @@ fn collect_model_info(
                     ));
                 }
 
+                if ident == model_ident {
+                    children = parse_child_fields(fields);
+                }
+
                 if *ident == state_name {
                     if !has_lera_attr(attrs, "state") {
                         return Err(format!(
@@ -272,15 +620,18 @@ fn collect_model_info(
                             state_name, file_path
                         ));
                     }
-                    // Determine whether #[lera::state] had the optional `samples` argument.
-                    if let Some(attr) = attrs.iter().find(|a| attr_is_lera(a, "state")) {
-                        // If args exist and are `samples`, mark enabled; otherwise false.
-                        if let Ok(ident) = attr.parse_args::<syn::Ident>() {
-                            if ident == "samples" {
-                                enable_samples = true;
-                            }
-                        }
+                    // Determine which optional arguments #[lera::state] had, e.g. `samples`.
+                    if let Some(attr) = attrs.iter().find(|a| attr_is_lera(a, "state"))
+                        && !matches!(attr.meta, syn::Meta::Path(_))
+                    {
+                        state_args = attr.parse_args::<StateAttrArgs>().map_err(|e| {
+                            format!(
+                                "Failed to parse #[lera::state] attribute on {} in {:?}: {}",
+                                state_name, file_path, e
+                            )
+                        })?;
                     }
+                    state_fields = parse_state_fields(fields);
                     found_state_struct = true;
                 }
             }
@@ -290,27 +641,47 @@ fn collect_model_info(
                 items,
                 ..
             }) => {
-                if has_lera_api(attrs) {
-                    if let Type::Path(TypePath { path, .. }) = self_ty.as_ref() {
-                        if path
-                            .segments
-                            .last()
-                            .map(|s| &s.ident)
-                            .map(|ident| ident == model_ident)
-                            .unwrap_or(false)
+                if has_lera_attr(attrs, "computed") && type_to_string(self_ty) == state_name {
+                    computed_fields.extend(items.iter().filter_map(|impl_item| {
+                        let syn::ImplItem::Fn(method) = impl_item else {
+                            return None;
+                        };
+                        let ReturnType::Type(_, ty) = &method.sig.output else {
+                            return None;
+                        };
+                        let name = method.sig.ident.to_string();
+                        Some(ParsedStateField {
+                            camel_name: to_camel_case(&name),
+                            rust_name: name,
+                            ty: (**ty).clone(),
+                        })
+                    }));
+                }
+
+                if has_lera_api(attrs)
+                    && let Type::Path(TypePath { path, .. }) = self_ty.as_ref()
+                    && path
+                        .segments
+                        .last()
+                        .map(|s| &s.ident)
+                        .map(|ident| ident == model_ident)
+                        .unwrap_or(false)
+                {
+                    found_api_impl = true;
+                    for impl_item in items {
+                        if let syn::ImplItem::Fn(method) = impl_item
+                            && let Visibility::Public(_) = method.vis
                         {
-                            found_api_impl = true;
-                            for impl_item in items {
-                                if let syn::ImplItem::Fn(method) = impl_item {
-                                    if let Visibility::Public(_) = method.vis {
-                                        if is_uniffi_constructor(&method.attrs) {
-                                            continue;
-                                        }
-
-                                        let method_name = method.sig.ident.to_string();
-                                        let camel_name = to_camel_case(&method_name);
-
-                                        let defaults_map = method
+                            if is_uniffi_constructor(&method.attrs)
+                                || is_lifecycle_hook(&method.attrs)
+                            {
+                                continue;
+                            }
+
+                            let method_name = method.sig.ident.to_string();
+                            let camel_name = to_camel_case(&method_name);
+
+                            let defaults_map = method
                                             .attrs
                                             .iter()
                                             .find(|attr| attr_is_lera(attr, "default_params"))
@@ -325,23 +696,18 @@ fn collect_model_info(
                                             .transpose()? // Option<Result<...>> -> Result<Option<...>>
                                             .map(|args| args.pairs);
 
-                                        let params = parse_method_parameters(
-                                            &method.sig,
-                                            defaults_map.as_ref(),
-                                        );
-                                        let return_metadata = parse_return_metadata(&method.sig);
-                                        let is_async = method.sig.asyncness.is_some();
-
-                                        methods.push(ParsedMethod {
-                                            rust_name: method_name,
-                                            camel_name,
-                                            params,
-                                            return_type: return_metadata,
-                                            is_async,
-                                        });
-                                    }
-                                }
-                            }
+                            let params =
+                                parse_method_parameters(&method.sig, defaults_map.as_ref());
+                            let return_metadata = parse_return_metadata(&method.sig);
+                            let is_async = method.sig.asyncness.is_some();
+
+                            methods.push(ParsedMethod {
+                                rust_name: method_name,
+                                camel_name,
+                                params,
+                                return_type: return_metadata,
+                                is_async,
+                            });
                         }
                     }
                 }
@@ -370,7 +736,18 @@ fn collect_model_info(
         listener_name,
         default_state_fn: to_default_state_fn_name(state_name),
         samples_state_fn: to_samples_state_fn_name(state_name),
-        enable_samples,
+        enable_samples: state_args.samples,
+        enable_diff: state_args.diff,
+        children,
+        navigation: None,
+        actions: None,
+        events: None,
+        enable_history: false,
+        enable_recorder: false,
+        enable_notify_policy: false,
+        computed_fields,
+        state_fields,
+        traits: ModelTraits::default(),
         methods,
         source_path: file_path.to_path_buf(),
     })
@@ -418,29 +795,48 @@ fn parse_return_metadata(sig: &syn::Signature) -> ParsedReturnType {
         ReturnType::Default => ParsedReturnType {
             ty: None,
             uses_result: false,
+            is_stream: false,
         },
-        ReturnType::Type(_, ty) => {
-            if let Some((ok_ty, _err_ty)) = try_extract_result_types(ty) {
-                let swift_ty = if is_unit_type(ok_ty) {
-                    None
-                } else {
-                    Some(ok_ty.clone())
-                };
-                ParsedReturnType {
-                    ty: swift_ty,
-                    uses_result: true,
-                }
-            } else if is_unit_type(ty) {
-                ParsedReturnType {
-                    ty: None,
-                    uses_result: false,
-                }
-            } else {
-                ParsedReturnType {
-                    ty: Some((**ty).clone()),
-                    uses_result: false,
-                }
-            }
+        // `#[lera::api]` runs a returned `Effect`, the method returns nothing.
+        ReturnType::Type(_, ty) if type_to_string(ty) == "Effect" => ParsedReturnType {
+            ty: None,
+            uses_result: false,
+            is_stream: false,
+        },
+        ReturnType::Type(_, ty) => match try_extract_stream_item(ty) {
+            // `#[lera::api]` exports a returned stream as an object iterated by `next`.
+            Some(item_ty) => ParsedReturnType {
+                is_stream: true,
+                ..parse_type_metadata(item_ty)
+            },
+            None => parse_type_metadata(ty),
+        },
+    }
+}
+
+fn parse_type_metadata(ty: &Type) -> ParsedReturnType {
+    if let Some((ok_ty, _err_ty)) = try_extract_result_types(ty) {
+        let swift_ty = if is_unit_type(ok_ty) {
+            None
+        } else {
+            Some(ok_ty.clone())
+        };
+        ParsedReturnType {
+            ty: swift_ty,
+            uses_result: true,
+            is_stream: false,
+        }
+    } else if is_unit_type(ty) {
+        ParsedReturnType {
+            ty: None,
+            uses_result: false,
+            is_stream: false,
+        }
+    } else {
+        ParsedReturnType {
+            ty: Some(ty.clone()),
+            uses_result: false,
+            is_stream: false,
         }
     }
 }
@@ -449,31 +845,133 @@ fn is_unit_type(ty: &Type) -> bool {
     matches!(ty, Type::Tuple(tuple) if tuple.elems.is_empty())
 }
 
+/// The `#[lera::state(...)]` arguments relevant to the generated ViewModels.
+#[derive(Default)]
+struct StateAttrArgs {
+    samples: bool,
+    diff: bool,
+    serde: bool,
+}
+
+impl Parse for StateAttrArgs {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let metas = Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated(input)?;
+        let has = |name: &str| metas.iter().any(|meta| meta.path().is_ident(name));
+        Ok(Self {
+            samples: has("samples"),
+            diff: has("diff"),
+            serde: has("serde"),
+        })
+    }
+}
+
 struct ModelAttrArgs {
     state_ty: Type,
+    traits: ModelTraits,
+    history: bool,
+    recorder: bool,
+    navigation: Option<syn::Ident>,
+    notify: bool,
+    actions: Option<Type>,
+    events: Option<Type>,
+}
+
+/// Traits exported for a model which the generated ViewModels forward to,
+/// see `traits(...)` in `#[lera::model]`.
+#[derive(Debug, Clone, Copy)]
+pub struct ModelTraits {
+    pub debug: bool,
+    pub display: bool,
+}
+
+impl Default for ModelTraits {
+    fn default() -> Self {
+        Self {
+            debug: true,
+            display: true,
+        }
+    }
 }
 
 impl Parse for ModelAttrArgs {
     fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
-        let key: syn::Ident = input.parse()?;
-        if key != "state" {
-            return Err(syn::Error::new(
-                key.span(),
-                "expected `state` argument, e.g. #[lera::model(state = MyState)]",
-            ));
-        }
+        let mut state_ty: Option<Type> = None;
+        let mut traits = ModelTraits::default();
+        let mut history = false;
+        let mut recorder = false;
+        let mut navigation = None;
+        let mut notify = false;
+        let mut actions = None;
+        let mut events = None;
 
-        input.parse::<syn::Token![=]>()?;
-        let state_ty: Type = input.parse()?;
+        while !input.is_empty() {
+            let key: syn::Ident = input.parse()?;
+            if key == "state" {
+                input.parse::<syn::Token![=]>()?;
+                state_ty = Some(input.parse()?);
+            } else if key == "traits" {
+                let content;
+                syn::parenthesized!(content in input);
+                let idents = Punctuated::<syn::Ident, syn::Token![,]>::parse_terminated(&content)?;
+                let has = |name: &str| idents.iter().any(|ident| ident == name);
+                traits = ModelTraits {
+                    debug: has("debug"),
+                    display: has("display"),
+                };
+            } else if key == "history" {
+                input.parse::<syn::Token![=]>()?;
+                input.parse::<syn::LitInt>()?;
+                history = true;
+            } else if key == "recorder" {
+                recorder = true;
+            } else if key == "navigation" {
+                input.parse::<syn::Token![=]>()?;
+                navigation = Some(input.parse()?);
+            } else if key == "notify" {
+                // `debounce(16ms)` or `throttle(16ms)`
+                input.parse::<syn::Token![=]>()?;
+                input.parse::<syn::Ident>()?;
+                let content;
+                syn::parenthesized!(content in input);
+                content.parse::<syn::LitInt>()?;
+                notify = true;
+            } else if key == "poison" {
+                // `recover` or `reset`, only applies in Rust.
+                input.parse::<syn::Token![=]>()?;
+                input.parse::<syn::Ident>()?;
+            } else if key == "actions" {
+                input.parse::<syn::Token![=]>()?;
+                actions = Some(input.parse()?);
+            } else if key == "events" {
+                input.parse::<syn::Token![=]>()?;
+                events = Some(input.parse()?);
+            } else {
+                return Err(syn::Error::new(
+                    key.span(),
+                    "unknown argument, expected `state = MyState`, `traits(...)`, `history = N`, `recorder`, `navigation = field`, `notify = debounce(16ms)`, `poison = reset`, `actions = MyAction` or `events = MyEvent`",
+                ));
+            }
 
-        if input.peek(syn::Token![,]) {
-            input.parse::<syn::Token![,]>()?;
-            if !input.is_empty() {
-                return Err(input.error("unexpected additional arguments"));
+            if input.is_empty() {
+                break;
             }
+            input.parse::<syn::Token![,]>()?;
         }
 
-        Ok(Self { state_ty })
+        let state_ty = state_ty.ok_or_else(|| {
+            input.error("expected `state` argument, e.g. #[lera::model(state = MyState)]")
+        })?;
+
+        Ok(Self {
+            state_ty,
+            traits,
+            history,
+            recorder,
+            navigation,
+            notify,
+            actions,
+            events,
+        })
     }
 }
 
@@ -509,6 +1007,39 @@ pub fn try_extract_result_types(ty: &Type) -> Option<(&Type, &Type)> {
     }
 }
 
+/// The item type of `impl Stream<Item = T>` or `lera::Stream<T>`.
+pub fn try_extract_stream_item(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::ImplTrait(impl_trait) => impl_trait.bounds.iter().find_map(|bound| {
+            let syn::TypeParamBound::Trait(bound) = bound else {
+                return None;
+            };
+            let segment = bound.path.segments.last()?;
+            if segment.ident != "Stream" {
+                return None;
+            }
+            let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+                return None;
+            };
+            args.args.iter().find_map(|arg| match arg {
+                syn::GenericArgument::AssocType(assoc) if assoc.ident == "Item" => Some(&assoc.ty),
+                _ => None,
+            })
+        }),
+        Type::Path(type_path) => {
+            let segment = type_path.path.segments.last()?;
+            if segment.ident != "Stream" {
+                return None;
+            }
+            match type_path_generic_args(segment).as_slice() {
+                [item_ty] => Some(item_ty),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
 pub fn type_to_string(ty: &Type) -> String {
     match ty {
         Type::Path(type_path) => type_path
@@ -1,11 +1,13 @@
 use askama::Template;
+use heck::ToSnakeCase;
 use quote::ToTokens;
 use std::path::Path;
 use syn::{Expr, ExprUnary, Type, TypePath, UnOp};
 
 use super::post_process_shared::{
-    DefaultParamValue, ParsedMethod, ParsedModel, ParsedReturnType, parse_lera_models,
-    to_camel_case, type_path_generic_args,
+    DefaultParamValue, ParsedActions, ParsedChild, ParsedMethod, ParsedModel, ParsedNavigation,
+    ParsedReturnType, parse_lera_models, parse_serde_states, to_camel_case,
+    to_computed_state_fn_name, type_path_generic_args,
 };
 
 #[derive(Debug, Clone)]
@@ -15,21 +17,95 @@ pub struct MethodParam {
     pub default_value: Option<String>,
 }
 
+#[derive(Debug, Clone)]
+pub struct SwiftStateField {
+    pub name: String,
+    pub swift_type: String,
+    /// Whether the field is an `Option`, always set by a `{State}Diff`.
+    pub is_optional: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct LeraModelInfo {
     pub model_name: String,
     pub state_name: String,
     pub listener_name: String,
+    /// What the listener receives, the state or with `diff` its `{State}Diff`,
+    /// with computed properties a `{State}Change` holding them.
+    pub change_name: String,
+    /// The name of the listener parameter receiving the change.
+    pub change_param: String,
+    /// The state, or its diff, in the forwarded `change`.
+    pub state_of_change: String,
     pub default_state_fn: String,
     pub samples_state_fn: String,
     pub enable_samples: bool,
+    pub enable_diff: bool,
+    pub enable_history: bool,
+    pub enable_recorder: bool,
+    pub enable_notify_policy: bool,
+    pub state_fields: Vec<SwiftStateField>,
+    /// Properties of `#[lera::computed]`, delivered with every change and
+    /// initially evaluated by `computed_fn`.
+    pub computed_fields: Vec<SwiftStateField>,
+    pub computed_fn: String,
+    /// The `#[lera::child]` models, exposed as child ViewModels.
+    pub children: Vec<ParsedChild>,
+    /// Whether another model has this one as a child, sharing its instance.
+    pub is_child: bool,
+    /// The `navigation = field` stack, bound to `NavigationStack(path:)`.
+    pub navigation: Option<ParsedNavigation>,
+    /// The `actions = MyAction` of the model, sent with `send(_:)`.
+    pub actions: Option<SwiftActions>,
+    /// The `events = MyEvent` of the model, exposed as an `AsyncStream`.
+    pub events: Option<String>,
+    pub exports_debug: bool,
+    pub exports_display: bool,
     pub methods: Vec<String>,
 }
 
+#[derive(Debug, Clone)]
+pub struct SwiftActions {
+    pub name: String,
+    /// A method per action, unless it has unnamed fields or its name is taken.
+    pub methods: Vec<SwiftActionMethod>,
+}
+
+/// A method sending one action, `func setCount(count: Int32)`.
+#[derive(Debug, Clone)]
+pub struct SwiftActionMethod {
+    pub name: String,
+    pub params: Vec<SwiftStateField>,
+    /// The action sent, `.setCount(count: count)`.
+    pub action: String,
+}
+
+/// A `#[lera::state(serde)]` state, made `Codable` with the same JSON keys.
+#[derive(Debug, Clone)]
+pub struct SwiftCodableState {
+    pub name: String,
+    pub coding_keys: Vec<SwiftCodingKey>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SwiftCodingKey {
+    pub name: String,
+    pub wire_name: String,
+}
+
 #[derive(Template)]
 #[template(path = "view_model.swift.jinja", escape = "none")]
 struct ViewModelTemplate {
     models: Vec<LeraModelInfo>,
+    codable_states: Vec<SwiftCodableState>,
+    /// Whether the crate declared `lera_setup_ffi_for_executor!()`.
+    uses_foreign_executor: bool,
+    /// Whether the crate declared `lera_setup_ffi_for_ui_dispatcher!()`.
+    uses_ui_dispatcher: bool,
+    /// Whether the crate declared `lera_setup_ffi_for_persistence!()`.
+    uses_persistence: bool,
+    /// Whether any model has a `navigation = field`, which needs SwiftUI.
+    uses_navigation: bool,
 }
 
 struct ReturnMetadata {
@@ -46,7 +122,10 @@ pub(crate) fn swift_transform(
     let parsed_models = parse_lera_models(path_to_target_rust_crate)?;
     println!("📝 Found {} LeraModel implementations", parsed_models.len());
 
-    let models: Vec<LeraModelInfo> = parsed_models.iter().map(build_model_info).collect();
+    let models: Vec<LeraModelInfo> = parsed_models
+        .iter()
+        .map(|model| build_model_info(model, &parsed_models))
+        .collect();
 
     for model in &models {
         println!(
@@ -56,7 +135,30 @@ pub(crate) fn swift_transform(
         );
     }
 
-    let template = ViewModelTemplate { models };
+    let codable_states = parse_serde_states(path_to_target_rust_crate)?
+        .into_iter()
+        .map(|state| SwiftCodableState {
+            name: state.name,
+            coding_keys: state
+                .fields
+                .into_iter()
+                .map(|field| SwiftCodingKey {
+                    name: field.camel_name,
+                    wire_name: field.rust_name,
+                })
+                .collect(),
+        })
+        .collect();
+
+    let uses_navigation = models.iter().any(|model| model.navigation.is_some());
+    let template = ViewModelTemplate {
+        models,
+        codable_states,
+        uses_foreign_executor: corpus.contains("func installForeignExecutor("),
+        uses_ui_dispatcher: corpus.contains("func installUiDispatcher("),
+        uses_persistence: corpus.contains("func installStateStorage("),
+        uses_navigation,
+    };
     let generated_swift = template
         .render()
         .map_err(|e| format!("Template rendering failed: {}", e))?;
@@ -67,7 +169,7 @@ pub(crate) fn swift_transform(
     Ok(result)
 }
 
-fn build_model_info(model: &ParsedModel) -> LeraModelInfo {
+fn build_model_info(model: &ParsedModel, models: &[ParsedModel]) -> LeraModelInfo {
     let methods = model
         .methods
         .iter()
@@ -78,9 +180,88 @@ fn build_model_info(model: &ParsedModel) -> LeraModelInfo {
         model_name: model.model_name.clone(),
         state_name: model.state_name.clone(),
         listener_name: model.listener_name.clone(),
+        change_name: model.change_name(),
+        change_param: model.change_param().to_owned(),
+        state_of_change: model.state_of_change(),
         default_state_fn: model.default_state_fn.clone(),
         samples_state_fn: model.samples_state_fn.clone(),
         enable_samples: model.enable_samples,
+        enable_diff: model.enable_diff,
+        enable_history: model.enable_history,
+        enable_recorder: model.enable_recorder,
+        enable_notify_policy: model.enable_notify_policy,
+        state_fields: model
+            .state_fields
+            .iter()
+            .map(|field| SwiftStateField {
+                name: field.camel_name.clone(),
+                swift_type: swift_type_from_syn_type(&field.ty),
+                is_optional: field.is_optional(),
+            })
+            .collect(),
+        computed_fields: model
+            .computed_fields
+            .iter()
+            .map(|field| SwiftStateField {
+                name: field.camel_name.clone(),
+                swift_type: swift_type_from_syn_type(&field.ty),
+                is_optional: field.is_optional(),
+            })
+            .collect(),
+        computed_fn: to_computed_state_fn_name(&model.state_name),
+        children: model.children.clone(),
+        is_child: model.is_child_of_any(models),
+        navigation: model.navigation.clone(),
+        actions: model
+            .actions
+            .as_ref()
+            .map(|actions| build_actions(actions, model)),
+        events: model.events.clone(),
+        exports_debug: model.traits.debug,
+        exports_display: model.traits.display,
+        methods,
+    }
+}
+
+fn build_actions(actions: &ParsedActions, model: &ParsedModel) -> SwiftActions {
+    let methods = actions
+        .actions
+        .iter()
+        .filter(|action| !action.is_tuple)
+        .map(|action| {
+            // UniFFI lowercases the first letter of enum cases.
+            let name = to_camel_case(&action.name.to_snake_case());
+            let params: Vec<SwiftStateField> = action
+                .fields
+                .iter()
+                .map(|field| SwiftStateField {
+                    name: field.camel_name.clone(),
+                    swift_type: swift_type_from_syn_type(&field.ty),
+                    is_optional: field.is_optional(),
+                })
+                .collect();
+            let action = if params.is_empty() {
+                format!(".{}", name)
+            } else {
+                let arguments = params
+                    .iter()
+                    .map(|param| format!("{0}: {0}", param.name))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!(".{}({})", name, arguments)
+            };
+            SwiftActionMethod {
+                name,
+                params,
+                action,
+            }
+        })
+        .filter(|method| {
+            method.name != "send" && model.methods.iter().all(|m| m.camel_name != method.name)
+        })
+        .collect();
+    SwiftActions {
+        name: actions.name.clone(),
         methods,
     }
 }
@@ -150,6 +331,16 @@ fn build_method(method: &ParsedMethod, model: &ParsedModel) -> String {
         format!("({}\n\t\t)", param_names.join(""))
     };
 
+    if method.return_type.is_stream {
+        return build_stream_method(
+            method,
+            &return_metadata,
+            &param_part,
+            async_keyword,
+            &call_params,
+        );
+    }
+
     let mut call_prefix_parts: Vec<&str> = Vec::new();
     if return_metadata.uses_throws {
         call_prefix_parts.push("try");
@@ -176,6 +367,55 @@ fn build_method(method: &ParsedMethod, model: &ParsedModel) -> String {
     )
 }
 
+/// Wraps the exported stream object returned by the method in an
+/// `AsyncThrowingStream`, which cancels it once its iteration is cancelled.
+fn build_stream_method(
+    method: &ParsedMethod,
+    return_metadata: &ReturnMetadata,
+    param_part: &str,
+    async_keyword: &str,
+    call_params: &str,
+) -> String {
+    let item_type = return_metadata.swift_type.as_deref().unwrap_or("Void");
+    let call_prefix = if method.is_async { "await " } else { "" };
+    let forward = if return_metadata.uses_throws {
+        "\t\t\t\tdo {\n\
+         \t\t\t\t\twhile let item = try await stream.next() {\n\
+         \t\t\t\t\t\tcontinuation.yield(item)\n\
+         \t\t\t\t\t}\n\
+         \t\t\t\t\tcontinuation.finish()\n\
+         \t\t\t\t} catch {\n\
+         \t\t\t\t\tcontinuation.finish(throwing: error)\n\
+         \t\t\t\t}"
+    } else {
+        "\t\t\t\twhile let item = await stream.next() {\n\
+         \t\t\t\t\tcontinuation.yield(item)\n\
+         \t\t\t\t}\n\
+         \t\t\t\tcontinuation.finish()"
+    };
+
+    format!(
+        "\tpublic func {}{}{} -> AsyncThrowingStream<{}, Error> {{\n\
+         \t\tlet stream = {}model.{}{}\n\
+         \t\treturn AsyncThrowingStream {{ continuation in\n\
+         \t\t\tlet task = Task {{\n{}\n\t\t\t}}\n\
+         \t\t\tcontinuation.onTermination = {{ _ in\n\
+         \t\t\t\ttask.cancel()\n\
+         \t\t\t\tstream.cancel()\n\
+         \t\t\t}}\n\
+         \t\t}}\n\
+         \t}}",
+        method.camel_name,
+        param_part,
+        async_keyword,
+        item_type,
+        call_prefix,
+        method.camel_name,
+        call_params,
+        forward
+    )
+}
+
 fn method_params_to_swift(method: &ParsedMethod, model: &ParsedModel) -> Vec<MethodParam> {
     method
         .params
@@ -357,10 +597,11 @@ fn default_expr_to_swift(expr: &Expr, swift_type: &str) -> Option<String> {
         }) => default_expr_to_swift(inner, swift_type).map(|value| format!("-{}", value)),
         Expr::Path(expr_path) => {
             let mut segments = expr_path.path.segments.iter();
-            if let Some(first) = segments.next() {
-                if first.ident == "None" && segments.next().is_none() {
-                    return Some("nil".to_string());
-                }
+            if let Some(first) = segments.next()
+                && first.ident == "None"
+                && segments.next().is_none()
+            {
+                return Some("nil".to_string());
             }
             None
         }
@@ -0,0 +1,66 @@
+use std::sync::{Arc, RwLock};
+
+/// Work to run on the UI thread, e.g. delivering state changes to listeners.
+pub type UiJob = Box<dyn FnOnce() + Send + 'static>;
+
+/// Runs jobs on the UI thread, installed with [`install_dispatcher`], or over
+/// FFI with `install_ui_dispatcher` declared by `lera_setup_ffi_for_ui_dispatcher!`.
+pub trait Dispatcher: Send + Sync {
+    fn dispatch(&self, job: UiJob);
+}
+
+static UI_DISPATCHER: RwLock<Option<Arc<dyn Dispatcher>>> = RwLock::new(None);
+
+/// Makes models deliver state changes through `dispatcher`, replacing any
+/// previously installed one. Without a dispatcher listeners are called on the
+/// thread which mutated the state.
+pub fn install_dispatcher(dispatcher: Arc<dyn Dispatcher>) {
+    *UI_DISPATCHER.write().expect("UI_DISPATCHER poisoned") = Some(dispatcher);
+}
+
+pub(crate) fn installed_dispatcher() -> Option<Arc<dyn Dispatcher>> {
+    UI_DISPATCHER
+        .read()
+        .expect("UI_DISPATCHER poisoned")
+        .clone()
+}
+
+#[macro_export]
+macro_rules! lera_setup_ffi_for_ui_dispatcher {
+    () => {
+        /// A job handed to the [`UiDispatcher`], which should `run` it on the UI thread.
+        #[derive(uniffi::Object)]
+        pub struct UiTask(std::sync::Mutex<Option<::lera::UiJob>>);
+
+        #[uniffi::export]
+        impl UiTask {
+            /// Runs the job, running it again is a no-op.
+            pub fn run(&self) {
+                let job = self.0.lock().expect("UiTask poisoned").take();
+                if let Some(job) = job {
+                    job();
+                }
+            }
+        }
+
+        /// Dispatcher trait that the foreign code implements, e.g. using the main actor.
+        #[uniffi::export(with_foreign)]
+        pub trait UiDispatcher: Send + Sync {
+            fn dispatch(&self, task: std::sync::Arc<UiTask>);
+        }
+
+        #[uniffi::export]
+        pub fn install_ui_dispatcher(dispatcher: std::sync::Arc<dyn UiDispatcher>) {
+            struct Bridge {
+                inner: std::sync::Arc<dyn UiDispatcher>,
+            }
+            impl ::lera::Dispatcher for Bridge {
+                fn dispatch(&self, job: ::lera::UiJob) {
+                    let task = UiTask(std::sync::Mutex::new(Some(job)));
+                    self.inner.dispatch(std::sync::Arc::new(task))
+                }
+            }
+            ::lera::install_dispatcher(std::sync::Arc::new(Bridge { inner: dispatcher }));
+        }
+    };
+}
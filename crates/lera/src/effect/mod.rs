@@ -0,0 +1,268 @@
+//! Side effects returned by reducers and `#[lera::api]` methods, run by lera
+//! as tasks of the model, feeding their results back into it.
+//!
+//! A [`Reducer`](crate::Reducer) returns an `Effect<Action>`, whose results are
+//! sent back as actions. An api method taking `self: &Arc<Self>` returns an
+//! `Effect<Mutation<State>>`, whose results mutate the state. Intercept the
+//! tasks of a model in tests, see [`Tasks::intercept`](crate::tasks::Tasks::intercept).
+
+use std::{
+    fmt,
+    future::Future,
+    sync::{Arc, Weak},
+};
+
+use futures::{
+    channel::oneshot,
+    future::{BoxFuture, join_all},
+};
+
+use crate::{LeraModel, tasks::TaskKey};
+
+/// Something to do after an action was reduced or an api method returned, e.g.
+/// run a future and then feed its result back into the model.
+///
+/// Futures are run as tasks of the model, cancelled when it is dropped, while
+/// everything else happens right away.
+#[must_use = "an effect does nothing unless returned to lera"]
+pub struct Effect<A>(Repr<A>);
+
+enum Repr<A> {
+    None,
+    Send(A),
+    Run(BoxFuture<'static, A>),
+    Cancellable(TaskKey, Box<Effect<A>>),
+    Cancel(TaskKey),
+    Merge(Vec<Effect<A>>),
+    Concat(Vec<Effect<A>>),
+}
+
+impl<A> Effect<A> {
+    /// Nothing, the action only changed the state.
+    pub fn none() -> Self {
+        Self(Repr::None)
+    }
+
+    /// Feeds `output` back right away.
+    pub fn send(output: A) -> Self {
+        Self(Repr::Send(output))
+    }
+
+    /// Runs `future`, then feeds its output back.
+    pub fn run(future: impl Future<Output = A> + Send + 'static) -> Self {
+        Self(Repr::Run(Box::pin(future)))
+    }
+
+    /// Cancels the effect running as `id`, if any.
+    pub fn cancel(id: impl Into<TaskKey>) -> Self {
+        Self(Repr::Cancel(id.into()))
+    }
+
+    /// Runs `effects` at the same time.
+    pub fn merge(effects: impl IntoIterator<Item = Self>) -> Self {
+        Self(Repr::Merge(effects.into_iter().collect()))
+    }
+
+    /// Runs `effects` one after the other, each once the previous one completed.
+    pub fn concat(effects: impl IntoIterator<Item = Self>) -> Self {
+        Self(Repr::Concat(effects.into_iter().collect()))
+    }
+
+    /// Runs the effect as a task with `id`, cancelling the effect already
+    /// running as `id`, and which [`Effect::cancel`] cancels.
+    pub fn cancellable(self, id: impl Into<TaskKey>) -> Self {
+        Self(Repr::Cancellable(id.into(), Box::new(self)))
+    }
+
+    /// Whether the effect does nothing, e.g. to assert on in tests.
+    pub fn is_none(&self) -> bool {
+        match &self.0 {
+            Repr::None => true,
+            Repr::Merge(effects) | Repr::Concat(effects) => effects.iter().all(Self::is_none),
+            _ => false,
+        }
+    }
+
+    /// The outputs fed back right away, not those of futures, e.g. to assert on
+    /// the actions a reducer sends in tests.
+    pub fn into_actions(self) -> Vec<A> {
+        match self.0 {
+            Repr::Send(output) => vec![output],
+            Repr::Cancellable(_, effect) => effect.into_actions(),
+            Repr::Merge(effects) | Repr::Concat(effects) => {
+                effects.into_iter().flat_map(Self::into_actions).collect()
+            }
+            Repr::None | Repr::Run(_) | Repr::Cancel(_) => Vec::new(),
+        }
+    }
+
+    /// Whether nothing of the effect has to wait for a future.
+    fn is_immediate(&self) -> bool {
+        match &self.0 {
+            Repr::None | Repr::Send(_) | Repr::Cancel(_) => true,
+            Repr::Run(_) | Repr::Cancellable(..) => false,
+            Repr::Merge(effects) | Repr::Concat(effects) => effects.iter().all(Self::is_immediate),
+        }
+    }
+}
+
+impl<S: 'static> Effect<Mutation<S>> {
+    /// Mutates the state right away.
+    pub fn mutate(mutate: impl FnOnce(&mut S) + Send + 'static) -> Self {
+        Self::send(Mutation::new(mutate))
+    }
+
+    /// Runs `future`, then mutates the state with its output.
+    pub fn run_then_mutate<T: Send + 'static>(
+        future: impl Future<Output = T> + Send + 'static,
+        mutate: impl FnOnce(&mut S, T) + Send + 'static,
+    ) -> Self {
+        Self::run(async move {
+            let output = future.await;
+            Mutation::new(move |state| mutate(state, output))
+        })
+    }
+}
+
+impl<A> Default for Effect<A> {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl<A: fmt::Debug> fmt::Debug for Effect<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Repr::None => f.write_str("Effect::none()"),
+            Repr::Send(output) => write!(f, "Effect::send({:?})", output),
+            Repr::Run(_) => f.write_str("Effect::run(..)"),
+            Repr::Cancellable(id, effect) => write!(f, "{:?}.cancellable({:?})", effect, id),
+            Repr::Cancel(id) => write!(f, "Effect::cancel({:?})", id),
+            Repr::Merge(effects) => write!(f, "Effect::merge({:?})", effects),
+            Repr::Concat(effects) => write!(f, "Effect::concat({:?})", effects),
+        }
+    }
+}
+
+/// A change of a state `S`, what the effects of api methods feed back.
+pub struct Mutation<S>(Box<dyn FnOnce(&mut S) + Send>);
+
+impl<S> Mutation<S> {
+    pub fn new(mutate: impl FnOnce(&mut S) + Send + 'static) -> Self {
+        Self(Box::new(mutate))
+    }
+
+    pub fn apply(self, state: &mut S) {
+        (self.0)(state)
+    }
+}
+
+impl<S> fmt::Debug for Mutation<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Mutation")
+    }
+}
+
+/// Feeds an output of an effect back into the model.
+pub(crate) type Feedback<M, A> = fn(&Arc<M>, A);
+
+/// Runs `effect` for `model`, its immediate parts right away and the rest as
+/// tasks of the model.
+pub(crate) fn execute<M, A>(model: &Arc<M>, effect: Effect<A>, feedback: Feedback<M, A>)
+where
+    M: LeraModel + Send + Sync + 'static,
+    A: Send + 'static,
+{
+    match effect.0 {
+        Repr::None => {}
+        Repr::Send(output) => feedback(model, output),
+        Repr::Cancel(id) => {
+            model.get_tasks().cancel(&id);
+        }
+        Repr::Merge(effects) => {
+            for effect in effects {
+                execute(model, effect, feedback);
+            }
+        }
+        Repr::Concat(mut effects) if effects.first().is_none_or(Effect::is_immediate) => {
+            // Runs the effects before the first one waiting for a future right away.
+            let waiting = effects
+                .iter()
+                .position(|effect| !effect.is_immediate())
+                .unwrap_or(effects.len());
+            let rest = effects.split_off(waiting);
+            for effect in effects {
+                execute(model, effect, feedback);
+            }
+            if !rest.is_empty() {
+                execute(model, Effect(Repr::Concat(rest)), feedback);
+            }
+        }
+        Repr::Cancellable(id, effect) => {
+            let task = run(Arc::downgrade(model), *effect, feedback);
+            model.get_tasks().spawn(id, task);
+        }
+        repr => {
+            let tasks = model.get_tasks();
+            let task = run(Arc::downgrade(model), Effect(repr), feedback);
+            tasks.spawn(tasks.anonymous_key(), task);
+        }
+    }
+}
+
+/// Runs `effect` to completion, only holding the model while feeding back.
+fn run<M, A>(model: Weak<M>, effect: Effect<A>, feedback: Feedback<M, A>) -> BoxFuture<'static, ()>
+where
+    M: LeraModel + Send + Sync + 'static,
+    A: Send + 'static,
+{
+    Box::pin(async move {
+        match effect.0 {
+            Repr::None => {}
+            Repr::Send(output) => {
+                if let Some(model) = model.upgrade() {
+                    feedback(&model, output);
+                }
+            }
+            Repr::Run(future) => {
+                let output = future.await;
+                if let Some(model) = model.upgrade() {
+                    feedback(&model, output);
+                }
+            }
+            Repr::Cancel(id) => {
+                if let Some(model) = model.upgrade() {
+                    model.get_tasks().cancel(&id);
+                }
+            }
+            Repr::Merge(effects) => {
+                join_all(
+                    effects
+                        .into_iter()
+                        .map(|effect| run(model.clone(), effect, feedback)),
+                )
+                .await;
+            }
+            Repr::Concat(effects) => {
+                for effect in effects {
+                    run(model.clone(), effect, feedback).await;
+                }
+            }
+            Repr::Cancellable(id, effect) => {
+                let (done, completed) = oneshot::channel::<()>();
+                {
+                    let Some(strong) = model.upgrade() else {
+                        return;
+                    };
+                    let task = run(model, *effect, feedback);
+                    strong.get_tasks().spawn(id, async move {
+                        task.await;
+                        let _ = done.send(());
+                    });
+                }
+                // Also completes once the task is cancelled, dropping `done`.
+                let _ = completed.await;
+            }
+        }
+    })
+}
@@ -0,0 +1,117 @@
+//! One-shot events from Rust to the UI, e.g. to show a toast, navigate or play
+//! a haptic, which unlike state changes are delivered once and then gone.
+//!
+//! Declare the enum of events with `#[lera::events]`, and the model with
+//! `#[lera::model(state = S, events = MyEvent)]`, then emit them with
+//! [`EventEmitter::emit`]. The generated ViewModels expose them as an
+//! `AsyncStream` in Swift and a `SharedFlow` in Kotlin.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use crate::{LeraModel, Listeners, Subscription};
+
+/// Receives the events of a model, implemented by the foreign `{Event}Listener`
+/// traits generated by `#[lera::events]`.
+pub trait EventListener: Send + Sync + 'static {
+    type Event: Clone + Send + 'static;
+
+    fn on_event(&self, event: Self::Event);
+}
+
+impl<T: EventListener + ?Sized> EventListener for Arc<T> {
+    type Event = T::Event;
+
+    fn on_event(&self, event: Self::Event) {
+        (**self).on_event(event)
+    }
+}
+
+/// Macro to generate the boilerplate implementation to bridge UniFFI traits to EventListener
+#[macro_export]
+macro_rules! impl_event_listener_bridge {
+    ($trait_name:ident, $event_type:ty) => {
+        impl ::lera::events::EventListener for dyn $trait_name {
+            type Event = $event_type;
+            fn on_event(&self, event: Self::Event) {
+                $trait_name::on_event(self, event)
+            }
+        }
+    };
+}
+
+/// A model declared with `events = MyEvent`, implemented by `#[lera::model]`.
+pub trait EventEmitter: LeraModel {
+    type EventListener: EventListener + Clone;
+
+    fn get_events(&self) -> &Events<Self::EventListener>;
+
+    /// Delivers `event` to every listener subscribed to the events of the
+    /// model, or buffers it until one subscribes.
+    fn emit(&self, event: <Self::EventListener as EventListener>::Event) {
+        self.get_events().emit(event);
+    }
+}
+
+/// The listeners of the events of a model, and the events emitted while none
+/// was subscribed.
+///
+/// Events are delivered on the thread emitting them, in the order they were
+/// emitted, so listeners must not emit events themselves.
+pub struct Events<L: EventListener> {
+    listeners: Listeners<L>,
+    buffer: Mutex<VecDeque<L::Event>>,
+}
+
+impl<L: EventListener> Default for Events<L> {
+    fn default() -> Self {
+        Self {
+            listeners: Listeners::default(),
+            buffer: Mutex::new(VecDeque::new()),
+        }
+    }
+}
+
+impl<L: EventListener + Clone> Events<L> {
+    pub fn emit(&self, event: L::Event) {
+        // Held while delivering, so that a listener subscribing meanwhile gets
+        // the buffered events before this one.
+        let mut buffer = self
+            .buffer
+            .lock()
+            .expect("Events::emit failed to acquire lock");
+        let listeners = self.listeners.snapshot();
+        if listeners.is_empty() {
+            buffer.push_back(event);
+            return;
+        }
+        for listener in listeners {
+            listener.on_event(event.clone());
+        }
+    }
+
+    /// Adds `listener`, delivering the buffered events to it first. It stays
+    /// subscribed until the returned [`Subscription`] is cancelled or dropped.
+    pub fn subscribe(&self, listener: L) -> Subscription {
+        let mut buffer = self
+            .buffer
+            .lock()
+            .expect("Events::subscribe failed to acquire lock");
+        for event in buffer.drain(..) {
+            listener.on_event(event);
+        }
+        self.listeners.subscribe(listener)
+    }
+
+    /// The events emitted while no listener was subscribed, not delivered.
+    pub fn buffered(&self) -> Vec<L::Event> {
+        self.buffer
+            .lock()
+            .expect("Events::buffered failed to acquire lock")
+            .iter()
+            .cloned()
+            .collect()
+    }
+}
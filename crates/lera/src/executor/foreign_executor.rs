@@ -0,0 +1,63 @@
+/// Declares the `ForeignExecutor` trait for Swift or Kotlin to implement, and
+/// `install_foreign_executor` to make lera run its futures on it.
+///
+/// The foreign side runs a `ForeignTask` by awaiting `run()` in a Swift `Task`
+/// or a Kotlin coroutine, and fires a `ForeignTimer` after the requested delay.
+#[macro_export]
+macro_rules! lera_setup_ffi_for_executor {
+    () => {
+        /// A Rust future for the [`ForeignExecutor`] to run.
+        #[derive(uniffi::Object)]
+        pub struct ForeignTask(std::sync::Mutex<Option<::lera::executor::BoxFuture>>);
+
+        #[uniffi::export]
+        impl ForeignTask {
+            /// Runs the future to completion, running it again is a no-op.
+            pub async fn run(&self) {
+                let future = self.0.lock().expect("ForeignTask poisoned").take();
+                if let Some(future) = future {
+                    future.await;
+                }
+            }
+        }
+
+        /// Completes a Rust sleep once fired by the [`ForeignExecutor`].
+        #[derive(uniffi::Object)]
+        pub struct ForeignTimer(::lera::executor::Signal);
+
+        #[uniffi::export]
+        impl ForeignTimer {
+            pub fn fire(&self) {
+                self.0.fire()
+            }
+        }
+
+        /// Executor trait that the foreign code implements, e.g. using Swift `Task`s.
+        #[uniffi::export(with_foreign)]
+        pub trait ForeignExecutor: Send + Sync {
+            fn spawn(&self, task: std::sync::Arc<ForeignTask>);
+            fn schedule(&self, delay_ms: u64, timer: std::sync::Arc<ForeignTimer>);
+        }
+
+        #[uniffi::export]
+        pub fn install_foreign_executor(executor: std::sync::Arc<dyn ForeignExecutor>) {
+            struct Bridge {
+                inner: std::sync::Arc<dyn ForeignExecutor>,
+            }
+            impl ::lera::executor::Executor for Bridge {
+                fn spawn(&self, future: ::lera::executor::BoxFuture) {
+                    let task = ForeignTask(std::sync::Mutex::new(Some(future)));
+                    self.inner.spawn(std::sync::Arc::new(task))
+                }
+                fn sleep(&self, duration: std::time::Duration) -> ::lera::executor::BoxFuture {
+                    let signal = ::lera::executor::Signal::default();
+                    let timer = ForeignTimer(signal.clone());
+                    let delay_ms = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+                    self.inner.schedule(delay_ms, std::sync::Arc::new(timer));
+                    Box::pin(signal.wait())
+                }
+            }
+            ::lera::executor::install_executor(std::sync::Arc::new(Bridge { inner: executor }));
+        }
+    };
+}
@@ -0,0 +1,182 @@
+//! The async runtime used by lera tasks and async `#[lera::api]` methods.
+//!
+//! Choose it with the cargo features of lera: `tokio` (default) or `smol`, which
+//! are exclusive, or with `foreign` run Rust futures on Swift `Task`s and Kotlin coroutines by
+//! declaring `lera_setup_ffi_for_executor!()` and installing the executor from
+//! the foreign side. Any [`Executor`] can also be installed with [`install_executor`].
+
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{
+        Arc, Mutex, RwLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+#[cfg(all(feature = "tokio", feature = "smol"))]
+compile_error!(
+    "the `tokio` and `smol` features of lera are exclusive, disable the default \
+    features to use smol: `default-features = false, features = [\"smol\"]`"
+);
+
+#[cfg(feature = "foreign")]
+mod foreign_executor;
+#[cfg(feature = "smol")]
+mod smol_executor;
+#[cfg(feature = "tokio")]
+mod tokio_executor;
+
+#[cfg(feature = "smol")]
+pub use smol_executor::SmolExecutor;
+#[cfg(feature = "tokio")]
+pub use tokio_executor::TokioExecutor;
+
+pub type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+pub trait Executor: Send + Sync + 'static {
+    /// Runs `future` to completion in the background.
+    fn spawn(&self, future: BoxFuture);
+
+    /// A future completing after `duration`.
+    fn sleep(&self, duration: Duration) -> BoxFuture;
+
+    /// Calls `poll` with the context of the executor entered, e.g. so that tokio
+    /// timers and IO work in futures polled by UniFFI.
+    fn enter(&self, poll: &mut dyn FnMut()) {
+        poll()
+    }
+}
+
+static EXECUTOR: RwLock<Option<Arc<dyn Executor>>> = RwLock::new(None);
+
+/// Makes lera use `executor`, replacing the one chosen by the cargo features.
+pub fn install_executor(executor: Arc<dyn Executor>) {
+    *EXECUTOR.write().expect("EXECUTOR poisoned") = Some(executor);
+}
+
+/// The installed executor, or else the one of the enabled cargo feature.
+pub fn executor() -> Arc<dyn Executor> {
+    if let Some(executor) = EXECUTOR.read().expect("EXECUTOR poisoned").clone() {
+        return executor;
+    }
+    let mut installed = EXECUTOR.write().expect("EXECUTOR poisoned");
+    installed.get_or_insert_with(default_executor).clone()
+}
+
+#[cfg(feature = "tokio")]
+fn default_executor() -> Arc<dyn Executor> {
+    Arc::new(TokioExecutor::shared())
+}
+
+#[cfg(all(feature = "smol", not(feature = "tokio")))]
+fn default_executor() -> Arc<dyn Executor> {
+    Arc::new(SmolExecutor)
+}
+
+#[cfg(not(any(feature = "tokio", feature = "smol")))]
+fn default_executor() -> Arc<dyn Executor> {
+    // Called once, until an executor is installed nothing runs.
+    log::error!(
+        "No lera executor installed, futures are dropped until one is. Enable the \
+        `tokio` or `smol` feature of lera, or install one, e.g. with \
+        `install_foreign_executor` of the `foreign` feature"
+    );
+    Arc::new(NoExecutor)
+}
+
+/// Used when no executor is installed and no feature provides one, rather than
+/// crashing the host app: spawned futures are dropped and sleeps never complete.
+#[cfg(not(any(feature = "tokio", feature = "smol")))]
+struct NoExecutor;
+
+#[cfg(not(any(feature = "tokio", feature = "smol")))]
+impl Executor for NoExecutor {
+    fn spawn(&self, _future: BoxFuture) {}
+
+    fn sleep(&self, _duration: Duration) -> BoxFuture {
+        Box::pin(std::future::pending())
+    }
+}
+
+pub fn spawn(future: impl Future<Output = ()> + Send + 'static) {
+    executor().spawn(Box::pin(future))
+}
+
+pub fn sleep(duration: Duration) -> BoxFuture {
+    executor().sleep(duration)
+}
+
+/// Polls `future` with the context of the executor entered, used by
+/// `#[lera::api]` for async methods, which UniFFI polls.
+pub fn with_context<T, F: Future<Output = T>>(future: F) -> WithContext<F> {
+    WithContext {
+        future: Box::pin(future),
+    }
+}
+
+pub struct WithContext<F> {
+    future: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for WithContext<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let mut output = Poll::Pending;
+        executor().enter(&mut || output = self.future.as_mut().poll(cx));
+        output
+    }
+}
+
+/// A one-shot signal, completing the futures of [`Signal::wait`] once fired.
+#[derive(Clone, Default)]
+pub struct Signal(Arc<SignalInner>);
+
+#[derive(Default)]
+struct SignalInner {
+    is_fired: AtomicBool,
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl Signal {
+    pub fn fire(&self) {
+        self.0.is_fired.store(true, Ordering::Release);
+        let wakers = std::mem::take(&mut *self.0.wakers.lock().expect("Signal poisoned"));
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    pub fn is_fired(&self) -> bool {
+        self.0.is_fired.load(Ordering::Acquire)
+    }
+
+    pub fn wait(&self) -> impl Future<Output = ()> + Send + 'static {
+        let signal = self.clone();
+        std::future::poll_fn(move |cx| {
+            if signal.is_fired() {
+                return Poll::Ready(());
+            }
+            signal
+                .0
+                .wakers
+                .lock()
+                .expect("Signal poisoned")
+                .push(cx.waker().clone());
+            // Fired while registering the waker.
+            if signal.is_fired() {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+    }
+}
+
+impl fmt::Debug for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Signal").field(&self.is_fired()).finish()
+    }
+}
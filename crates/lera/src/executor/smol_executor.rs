@@ -0,0 +1,19 @@
+use std::time::Duration;
+
+use super::{BoxFuture, Executor};
+
+/// Runs futures on the global executor of smol.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SmolExecutor;
+
+impl Executor for SmolExecutor {
+    fn spawn(&self, future: BoxFuture) {
+        smol::spawn(future).detach();
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture {
+        Box::pin(async move {
+            smol::Timer::after(duration).await;
+        })
+    }
+}
@@ -0,0 +1,46 @@
+use std::{sync::OnceLock, time::Duration};
+
+use tokio::runtime::{Builder, Handle};
+
+use super::{BoxFuture, Executor};
+
+/// Runs futures on a tokio runtime, by default one shared by all of lera.
+#[derive(Clone, Debug)]
+pub struct TokioExecutor {
+    handle: Handle,
+}
+
+impl TokioExecutor {
+    pub fn new(handle: Handle) -> Self {
+        Self { handle }
+    }
+
+    pub(crate) fn shared() -> Self {
+        static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+        let runtime = RUNTIME.get_or_init(|| {
+            Builder::new_multi_thread()
+                .enable_all()
+                .thread_name("lera-rt")
+                .build()
+                .expect("Failed to build lera tokio runtime")
+        });
+        Self::new(runtime.handle().clone())
+    }
+}
+
+impl Executor for TokioExecutor {
+    fn spawn(&self, future: BoxFuture) {
+        self.handle.spawn(future);
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture {
+        // Timers register with the runtime they are created in.
+        let _guard = self.handle.enter();
+        Box::pin(tokio::time::sleep(duration))
+    }
+
+    fn enter(&self, poll: &mut dyn FnMut()) {
+        let _guard = self.handle.enter();
+        poll()
+    }
+}
@@ -0,0 +1,71 @@
+use std::{collections::VecDeque, sync::Mutex};
+
+/// Bounded undo/redo history of a model state, enabled with
+/// `#[lera::model(state = S, history = 50)]`.
+pub struct History<S> {
+    inner: Mutex<HistoryInner<S>>,
+}
+
+struct HistoryInner<S> {
+    capacity: usize,
+    undo: VecDeque<S>,
+    redo: Vec<S>,
+}
+
+impl<S> History<S> {
+    /// A history keeping at most `capacity` previous states.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Mutex::new(HistoryInner {
+                capacity,
+                undo: VecDeque::with_capacity(capacity),
+                redo: Vec::new(),
+            }),
+        }
+    }
+
+    /// Records the state before a change, forgetting the oldest state when full
+    /// and all states which could have been redone.
+    pub fn record(&self, previous: S) {
+        let mut inner = self.lock();
+        if inner.undo.len() == inner.capacity {
+            inner.undo.pop_front();
+        }
+        inner.undo.push_back(previous);
+        inner.redo.clear();
+    }
+
+    /// Returns the state to restore, remembering `current` for [`History::redo`].
+    pub fn undo(&self, current: S) -> Option<S> {
+        let mut inner = self.lock();
+        let previous = inner.undo.pop_back()?;
+        inner.redo.push(current);
+        Some(previous)
+    }
+
+    /// Returns the state to restore, remembering `current` for [`History::undo`].
+    pub fn redo(&self, current: S) -> Option<S> {
+        let mut inner = self.lock();
+        let next = inner.redo.pop()?;
+        inner.undo.push_back(current);
+        Some(next)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.lock().undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.lock().redo.is_empty()
+    }
+
+    pub fn clear(&self) {
+        let mut inner = self.lock();
+        inner.undo.clear();
+        inner.redo.clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HistoryInner<S>> {
+        self.inner.lock().expect("History failed to acquire lock")
+    }
+}
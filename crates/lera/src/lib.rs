@@ -1,12 +1,50 @@
 mod bindgen;
 pub use bindgen::{build_android, build_swift};
-pub use lera_macros::{api, default_params, model, state};
+pub use lera_macros::{
+    actions, api, computed, default_params, error, events, model, navigation, on_drop, on_init,
+    state,
+};
 pub use lera_uniffi_build::{AndroidBuildSettings, AndroidTarget, SwiftBuildSettings};
-use log::debug;
 pub use samples_core::Samples;
-use std::sync::{Arc, RwLock};
+use std::{
+    future::Future,
+    sync::{Arc, Weak},
+    time::Duration,
+};
+mod dispatcher;
+pub use dispatcher::*;
+pub mod effect;
+pub use effect::{Effect, Mutation};
+pub mod events;
+pub use events::EventEmitter;
+pub mod executor;
+mod history;
+pub use history::*;
 mod logging;
 pub use logging::*;
+mod notifier;
+pub use notifier::*;
+pub mod panics;
+pub mod persistence;
+use persistence::Persistence;
+mod poison;
+use poison::write_state;
+pub use poison::{LeraError, PoisonPolicy};
+pub mod recording;
+use recording::{Recorder, Recording};
+mod reducer;
+pub use reducer::Reducer;
+mod store;
+use store::StateWriter;
+pub use store::{Snapshot, StateStore};
+pub mod stream;
+pub use stream::Stream;
+mod subscription;
+pub use subscription::*;
+pub mod tasks;
+use tasks::{ShouldContinue, TaskKey};
+mod transaction;
+pub use transaction::*;
 
 pub mod fmt_utils {
     use core::fmt;
@@ -41,8 +79,97 @@ pub mod fmt_utils {
     }
 }
 
-pub trait ModelState: std::fmt::Debug + Clone + PartialEq + Default {}
-impl<T: std::fmt::Debug + Clone + PartialEq + Default> ModelState for T {}
+/// Implementation details used by code generated by the `lera` procmacros.
+#[doc(hidden)]
+pub mod __private {
+    use std::sync::Arc;
+
+    pub use serde;
+
+    /// Fallback lifecycle hooks for models without `#[lera::on_init]` or
+    /// `#[lera::on_drop]` methods.
+    ///
+    /// The hook attributes generate inherent `__lera_on_init` / `__lera_on_drop`
+    /// methods, and inherent methods take precedence over trait methods, so the
+    /// code generated by `#[lera::model]` calls the user's hook when one is
+    /// declared and these no-ops otherwise.
+    pub trait LifecycleHooks {
+        fn __lera_on_init(self: &Arc<Self>) {}
+        fn __lera_on_drop(&self) {}
+    }
+    impl<T: ?Sized> LifecycleHooks for T {}
+
+    /// Fallbacks for models without a recorder, leaving `#[lera::api]` methods
+    /// as they are.
+    ///
+    /// `#[lera::model(recorder)]` generates inherent methods of the same names,
+    /// which mark the thread as running the method for the recorder, and take
+    /// precedence over these, like for [`LifecycleHooks`].
+    pub trait ApiMethods {
+        fn __lera_enter_api_method(_name: &'static str) {}
+
+        fn __lera_in_api_method<F: std::future::Future>(_name: &'static str, future: F) -> F {
+            future
+        }
+    }
+    impl<T: ?Sized> ApiMethods for T {}
+
+    /// The change of state `S` delivered to its listeners, implemented by
+    /// `#[lera::state]` and, with its properties, by `#[lera::computed]`.
+    pub trait StateChangeOf<S>: Sized {
+        fn change(old: &S, new: &S) -> Self;
+        fn merge(older: Self, newer: Self) -> Self;
+        fn apply(change: Self, state: &mut S);
+    }
+
+    /// Applies the change of a `#[lera::child]` model to its projection in the
+    /// state of `model`, the only way it changes, neither undone nor redone.
+    pub fn project_child<M: super::LeraModel + ?Sized>(
+        model: &M,
+        project: impl FnOnce(&mut M::State),
+    ) {
+        {
+            let writer = super::write_state(model);
+            let prev = writer.current();
+            let mut next = prev.state().clone();
+            project(&mut next);
+            super::publish(model, &writer, &prev, next);
+        }
+        super::did_commit(model);
+    }
+}
+
+pub trait ModelState:
+    std::fmt::Debug + Clone + PartialEq + Default + StateChange + Persistence
+{
+}
+impl<T: std::fmt::Debug + Clone + PartialEq + Default + StateChange + Persistence> ModelState
+    for T
+{
+}
+
+/// What listeners receive when a state changes, implemented by `#[lera::state]`.
+///
+/// By default that is the new state itself, with `#[lera::state(diff)]` it is
+/// the generated `{State}Diff` holding only the fields that changed.
+pub trait StateChange: Sized + PartialEq {
+    type Change: std::fmt::Debug + Clone + Send + Sync + 'static;
+    fn change(old: &Self, new: &Self) -> Self::Change;
+
+    /// Combines two consecutive changes into one, used to deliver only the
+    /// latest state when changes pile up waiting for the UI thread.
+    fn merge(older: Self::Change, newer: Self::Change) -> Self::Change;
+
+    /// Applies `change` to `state`, the state it was made from, used to project
+    /// the changes of a `#[lera::child]` model into the state of its parent.
+    fn apply(change: Self::Change, state: &mut Self);
+
+    /// Whether a mutation changed the state, comparing them with `PartialEq`
+    /// unless the state is declared with `#[lera::state(skip_compare)]`.
+    fn has_changed(old: &Self, new: &Self) -> bool {
+        old != new
+    }
+}
 
 /// Macro to generate the boilerplate implementation to bridge UniFFI traits to StateChangeListener
 #[macro_export]
@@ -51,8 +178,12 @@ macro_rules! impl_state_change_listener_bridge {
         // Only implement for the trait object to avoid conflicts
         impl ::lera::StateChangeListener for dyn $trait_name {
             type State = $state_type;
-            fn on_state_change(&self, new_state: Self::State) {
-                $trait_name::on_state_change(self, new_state)
+            fn on_state_change(
+                &self,
+                change: <Self::State as ::lera::StateChange>::Change,
+                version: ::lera::StateVersion,
+            ) {
+                $trait_name::on_state_change(self, change, version)
             }
         }
     };
@@ -60,57 +191,409 @@ macro_rules! impl_state_change_listener_bridge {
 
 pub trait StateChangeListener: Send + Sync + 'static {
     type State: ModelState;
-    fn on_state_change(&self, new_state: Self::State);
+
+    /// Called with every state change, `version` increases by one with each state
+    /// change and changes are delivered in version order.
+    fn on_state_change(&self, change: <Self::State as StateChange>::Change, version: StateVersion);
 }
 
 impl<T: StateChangeListener + ?Sized> StateChangeListener for Arc<T> {
     type State = T::State;
-    fn on_state_change(&self, new_state: Self::State) {
-        (**self).on_state_change(new_state)
+    fn on_state_change(&self, change: <Self::State as StateChange>::Change, version: StateVersion) {
+        (**self).on_state_change(change, version)
     }
 }
 
 pub trait LeraModel {
     type State: ModelState;
-    type Listener: StateChangeListener<State = Self::State>;
+    type Listener: StateChangeListener<State = Self::State> + Clone;
 
     fn new(state: Self::State, listener: Self::Listener) -> Arc<Self>
     where
         Self: Sized;
 
-    fn get_state_change_listener(&self) -> &Self::Listener;
-    fn get_state_guard(&self) -> &Arc<RwLock<Self::State>>;
+    fn get_notifier(&self) -> &Notifier<Self::Listener>;
+    fn get_store(&self) -> &StateStore<Self::State>;
+    fn get_tasks(&self) -> &tasks::Tasks;
+
+    /// The undo/redo history, `None` unless the model is declared with
+    /// `#[lera::model(state = S, history = N)]`.
+    fn get_history(&self) -> Option<&History<Self::State>> {
+        None
+    }
+
+    /// The recorder of state changes, `None` unless the model is declared with
+    /// `#[lera::model(state = S, recorder)]`.
+    fn get_recorder(&self) -> Option<&Recorder<Self::State>> {
+        None
+    }
+
+    /// When listeners are notified of state changes, immediately unless the
+    /// model is declared with `notify = debounce(...)` or `throttle(...)`.
+    fn notify_policy(&self) -> NotifyPolicy {
+        NotifyPolicy::Immediate
+    }
+
+    /// What happens to the state once a panic poisoned its store, kept as is
+    /// unless the model is declared with `poison = reset`.
+    fn poison_policy(&self) -> PoisonPolicy {
+        PoisonPolicy::Recover
+    }
+
+    /// Keeps the fields of `next` projecting `#[lera::child]` models as they
+    /// are in `prev`, since only the changes of the children change them.
+    fn keep_child_projections(&self, _prev: &Self::State, _next: &mut Self::State) {}
+
+    /// Delivers the state changes delayed by the [`NotifyPolicy`] right away.
+    fn flush(&self) {
+        if self.notify_policy() == NotifyPolicy::Immediate {
+            return;
+        }
+        self.get_tasks().cancel(&NOTIFY_TASK.into());
+        self.get_notifier().stop_throttling();
+        self.get_notifier().deliver_pending_merged();
+    }
+
+    /// Adds another listener next to the one passed to [`LeraModel::new`], it is
+    /// notified of every state change until the returned [`Subscription`] is
+    /// cancelled or dropped.
+    fn subscribe(&self, listener: Self::Listener) -> Subscription {
+        self.get_notifier().subscribe(listener)
+    }
 
+    /// The version of the latest state change, `0` until the state first changes.
+    fn state_version(&self) -> StateVersion {
+        self.get_notifier().version()
+    }
+
+    /// The latest state, read without locking.
+    fn snapshot(&self) -> Arc<Snapshot<Self::State>> {
+        self.get_store().load()
+    }
+
+    /// Calls `read` with the latest state, without locking or cloning it.
+    fn read<R>(&self, read: impl FnOnce(&Self::State) -> R) -> R {
+        self.get_store().read(read)
+    }
+
+    /// Calls `access` with a copy of the state, prefer [`LeraModel::read`].
     fn access<R: Clone>(&self, access: impl FnOnce(Self::State) -> R) -> R {
-        access(
-            self.get_state_guard()
-                .read()
-                .expect("LeraModel::access failed to acquire read lock")
-                .clone(),
-        )
+        access(self.read(Clone::clone))
     }
 
+    /// Like [`LeraModel::access`], but fails if the state is poisoned, see
+    /// [`PoisonPolicy`].
+    fn try_access<R: Clone>(&self, access: impl FnOnce(Self::State) -> R) -> Result<R, LeraError> {
+        if self.get_store().is_poisoned() {
+            return Err(LeraError::state_poisoned::<Self>());
+        }
+        Ok(self.access(access))
+    }
+
+    /// Calls `mutate` with a copy of the state, committed as the next snapshot
+    /// and notified to listeners if it changed, see [`StateChange::has_changed`].
+    /// A state poisoned by a panic is handled according to the [`PoisonPolicy`]
+    /// first.
+    ///
+    /// Copying the state is the one clone a mutation costs, in exchange for
+    /// snapshots that readers hold without locking.
     fn mutate<R>(&self, mutate: impl FnOnce(&mut Self::State) -> R) -> R {
-        let (out, should_notify, new_state) = {
-            let mut write_guard = self
-                .get_state_guard()
-                .write()
-                .expect("LeraModel::mutate failed to acquire write lock");
-            let prev_state = write_guard.clone();
-            let out = mutate(&mut write_guard);
-            let new_state = write_guard.clone();
-            let should_notify = new_state != prev_state;
-            (out, should_notify, new_state)
+        let out = mutate_locked(self, write_state(self), mutate);
+        did_commit(self);
+        out
+    }
+
+    /// Like [`LeraModel::mutate`], but fails instead of applying the
+    /// [`PoisonPolicy`] when the state is poisoned.
+    fn try_mutate<R>(&self, mutate: impl FnOnce(&mut Self::State) -> R) -> Result<R, LeraError> {
+        let writer = self
+            .get_store()
+            .lock()
+            .map_err(|_| LeraError::state_poisoned::<Self>())?;
+        let out = mutate_locked(self, writer, mutate);
+        did_commit(self);
+        Ok(out)
+    }
+
+    /// Runs `transaction` with exclusive access to the state, listeners are
+    /// notified at most once, when it returns `Ok` with a changed state. If it
+    /// returns `Err` all of its mutations are rolled back.
+    ///
+    /// ```ignore
+    /// self.transaction(|tx| {
+    ///     tx.mutate(|state| state.is_auto_incrementing = false);
+    ///     tx.mutate(|state| state.count = 0);
+    ///     Ok::<_, Infallible>(())
+    /// })
+    /// ```
+    fn transaction<R, E>(
+        &self,
+        transaction: impl FnOnce(&mut Transaction<'_, Self::State>) -> Result<R, E>,
+    ) -> Result<R, E> {
+        let result = {
+            let mut tx = Transaction::new(write_state(self));
+            let result = transaction(&mut tx);
+            // Rolled back by not committing.
+            if result.is_ok() {
+                let (writer, initial, current) = tx.into_parts();
+                commit_recorded(self, &writer, initial, current);
+            }
+            result
         };
 
-        if should_notify {
-            self.notify_state_change(new_state);
+        did_commit(self);
+        result
+    }
+
+    /// Restores the state before the latest change, returns `false` if there is
+    /// nothing to undo. Listeners are notified like for any other change.
+    fn undo(&self) -> bool {
+        restore_from_history(self, History::undo)
+    }
+
+    /// Restores the state most recently undone, returns `false` if there is
+    /// nothing to redo. Any other change clears what could be redone.
+    fn redo(&self) -> bool {
+        restore_from_history(self, History::redo)
+    }
+
+    fn can_undo(&self) -> bool {
+        self.get_history().is_some_and(History::can_undo)
+    }
+
+    fn can_redo(&self) -> bool {
+        self.get_history().is_some_and(History::can_redo)
+    }
+
+    /// Starts recording every state change, see [`Recording`]. Does nothing unless
+    /// the model is declared with `recorder`.
+    fn start_recording(&self) {
+        if let Some(recorder) = self.get_recorder() {
+            recorder.start();
         }
-        out
     }
 
-    fn notify_state_change(&self, new_state: Self::State) {
-        debug!("Notifying listener of state change: {:?}", new_state);
-        self.get_state_change_listener().on_state_change(new_state);
+    /// Stops recording, returns the state changes recorded since
+    /// [`LeraModel::start_recording`].
+    fn stop_recording(&self) -> Recording<Self::State> {
+        self.get_recorder().map(Recorder::stop).unwrap_or_default()
+    }
+
+    fn is_recording(&self) -> bool {
+        self.get_recorder().is_some_and(Recorder::is_recording)
+    }
+
+    /// Replays the recorded changes, setting the state to each recorded state in
+    /// turn and notifying listeners of it. Create the model with
+    /// [`Recording::initial_state`] to start from where the recording started.
+    /// The projections of child models are kept, like for any other change.
+    fn replay(&self, recording: &Recording<Self::State>) {
+        for change in &recording.changes {
+            self.mutate(|state| *state = change.after.clone());
+        }
+    }
+
+    /// Runs the future made by `task` on the [`executor`], cancelled when the
+    /// model is dropped. The task gets the model weakly, returns a key for
+    /// [`LeraModel::cancel_task`].
+    fn spawn<Fut>(self: &Arc<Self>, task: impl FnOnce(Weak<Self>) -> Fut) -> TaskKey
+    where
+        Self: Sized,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let key = self.get_tasks().anonymous_key();
+        self.spawn_keyed(key.clone(), task);
+        key
+    }
+
+    /// Like [`LeraModel::spawn`], but cancels any task already running with `key`.
+    fn spawn_keyed<Fut>(
+        self: &Arc<Self>,
+        key: impl Into<TaskKey>,
+        task: impl FnOnce(Weak<Self>) -> Fut,
+    ) where
+        Self: Sized,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.get_tasks()
+            .spawn(key.into(), task(Arc::downgrade(self)));
+    }
+
+    /// Calls `tick` with the model every `period`, cancelling any task already
+    /// running with `key`, until it returns `false` or the model is dropped.
+    fn spawn_interval(
+        self: &Arc<Self>,
+        key: impl Into<TaskKey>,
+        period: Duration,
+        tick: impl Fn(&Arc<Self>) -> ShouldContinue + Send + 'static,
+    ) where
+        Self: Sized + Send + Sync + 'static,
+    {
+        let model = Arc::downgrade(self);
+        self.get_tasks()
+            .spawn_interval(key.into(), period, move || {
+                model.upgrade().is_some_and(|model| tick(&model))
+            });
+    }
+
+    /// Runs `effect`, e.g. returned by an api method, mutating the state with
+    /// its results. Its futures run as tasks of the model.
+    fn run_effect(self: &Arc<Self>, effect: Effect<Mutation<Self::State>>)
+    where
+        Self: Sized + Send + Sync + 'static,
+    {
+        effect::execute(self, effect, |model, mutation| {
+            model.mutate(|state| mutation.apply(state))
+        });
+    }
+
+    /// Cancels the task running with `key`, returns whether there was one.
+    fn cancel_task(&self, key: impl Into<TaskKey>) -> bool {
+        self.get_tasks().cancel(&key.into())
+    }
+
+    fn is_task_running(&self, key: impl Into<TaskKey>) -> bool {
+        self.get_tasks().is_running(&key.into())
+    }
+}
+
+/// Everything that follows a state change, called once the new state is
+/// readable but while still holding the write lock, so that versions and
+/// recordings follow mutation order.
+fn did_change<M: LeraModel + ?Sized>(model: &M, old: &M::State, new: &M::State) {
+    model.get_notifier().enqueue(StateChange::change(old, new));
+    if let Some(recorder) = model.get_recorder() {
+        recorder.record(old, new);
     }
 }
+
+/// Runs `mutate` on a copy of the latest state, committing it if it changed.
+/// The copy is the only clone of the state, the snapshot read by others is left
+/// untouched and dropped once no reader holds it.
+fn mutate_locked<M: LeraModel + ?Sized, R>(
+    model: &M,
+    writer: StateWriter<'_, M::State>,
+    mutate: impl FnOnce(&mut M::State) -> R,
+) -> R {
+    let prev = writer.current();
+    let mut next = prev.state().clone();
+    let out = mutate(&mut next);
+    commit_recorded(model, &writer, prev, next);
+    out
+}
+
+/// Commits `next` as the state following `prev` if it changed, returns whether
+/// it did. Writes to the projections of child models are discarded.
+fn commit<M: LeraModel + ?Sized>(
+    model: &M,
+    writer: &StateWriter<'_, M::State>,
+    prev: &M::State,
+    mut next: M::State,
+) -> bool {
+    model.keep_child_projections(prev, &mut next);
+    publish(model, writer, prev, next)
+}
+
+/// Like [`commit`], including the projections of child models.
+fn publish<M: LeraModel + ?Sized>(
+    model: &M,
+    writer: &StateWriter<'_, M::State>,
+    prev: &M::State,
+    next: M::State,
+) -> bool {
+    if !StateChange::has_changed(prev, &next) {
+        return false;
+    }
+    // Published first, so that listeners, possibly notified by another thread
+    // draining the queue, never read an older state than the one they received.
+    let next = writer.commit(next);
+    did_change(model, prev, &next);
+    true
+}
+
+/// Like [`commit`], recording `prev` in the history so that it can be undone.
+fn commit_recorded<M: LeraModel + ?Sized>(
+    model: &M,
+    writer: &StateWriter<'_, M::State>,
+    prev: Arc<Snapshot<M::State>>,
+    next: M::State,
+) {
+    if commit(model, writer, &prev, next)
+        && let Some(history) = model.get_history()
+    {
+        history.record(Snapshot::into_state(prev));
+    }
+}
+
+/// Everything that follows committing, called once the write lock is released:
+/// saves the latest state and delivers the queued changes.
+fn did_commit<M: LeraModel + ?Sized>(model: &M) {
+    model.get_store().save_latest();
+    notify(model);
+}
+
+/// The task delaying notifications according to the [`NotifyPolicy`].
+const NOTIFY_TASK: &str = "lera.notify";
+
+/// Delivers the changes queued by [`did_change`], right away or according to
+/// the [`NotifyPolicy`] of the model.
+fn notify<M: LeraModel + ?Sized>(model: &M) {
+    let notifier = model.get_notifier();
+    match model.notify_policy() {
+        NotifyPolicy::Immediate => notifier.deliver_pending(),
+        NotifyPolicy::Debounce(delay) => {
+            // Replaces the pending delivery, if any.
+            let notifier = notifier.clone();
+            model.get_tasks().spawn(NOTIFY_TASK.into(), async move {
+                executor::sleep(delay).await;
+                notifier.deliver_pending_merged();
+            });
+        }
+        NotifyPolicy::Throttle(period) => {
+            if !notifier.start_throttling() {
+                // Delivered by the running throttling task.
+                return;
+            }
+            notifier.deliver_pending_merged();
+            let notifier = notifier.clone();
+            model.get_tasks().spawn(NOTIFY_TASK.into(), async move {
+                loop {
+                    executor::sleep(period).await;
+                    if !notifier.has_pending() {
+                        notifier.stop_throttling();
+                        // A change queued before throttling stopped was left for us.
+                        if !notifier.has_pending() || !notifier.start_throttling() {
+                            break;
+                        }
+                    }
+                    notifier.deliver_pending_merged();
+                }
+            });
+        }
+    }
+}
+
+/// Replaces the state with the one `step` takes from the model's history,
+/// notifying listeners of the change.
+fn restore_from_history<M: LeraModel + ?Sized>(
+    model: &M,
+    step: impl FnOnce(&History<M::State>, M::State) -> Option<M::State>,
+) -> bool {
+    let Some(history) = model.get_history() else {
+        return false;
+    };
+    let restored = {
+        let writer = write_state(model);
+        let current = writer.current();
+        match step(history, current.state().clone()) {
+            Some(state) => {
+                commit(model, &writer, &current, state);
+                true
+            }
+            None => false,
+        }
+    };
+
+    did_commit(model);
+    restored
+}
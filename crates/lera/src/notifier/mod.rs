@@ -0,0 +1,252 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc, Mutex, Weak,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use log::debug;
+
+use crate::{Listeners, StateChange, StateChangeListener, Subscription, installed_dispatcher};
+
+/// Monotonically increasing version of a model state, the state passed to
+/// `LeraModel::new` has version `0` and every notified state change increments it.
+pub type StateVersion = u64;
+
+/// When the listeners of a model are notified of state changes, set with
+/// `#[lera::model(state = S, notify = debounce(16ms))]` or `throttle(...)`.
+///
+/// Delayed changes are merged into one, the state itself changes right away.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NotifyPolicy {
+    /// After every change.
+    #[default]
+    Immediate,
+    /// Once no change has been made for the duration.
+    Debounce(Duration),
+    /// Right away, then at most once per duration while changes keep coming.
+    Throttle(Duration),
+}
+
+/// Delivers the state changes of a model to all of its listeners, tagging each
+/// with a [`StateVersion`].
+///
+/// Changes are delivered in version order, even when several threads mutate the
+/// model concurrently: versions are assigned when a change is enqueued, and only
+/// one thread at a time drains the queue. A listener mutating the model from
+/// within `on_state_change` does not deadlock, its change is delivered once the
+/// current delivery returns.
+///
+/// When a [`Dispatcher`](crate::Dispatcher) is installed, changes are delivered
+/// on the UI thread instead, and all changes enqueued while waiting for it are
+/// merged into one, carrying the latest version.
+pub struct Notifier<L: StateChangeListener> {
+    inner: Arc<NotifierInner<L>>,
+}
+
+type Change<L> = <<L as StateChangeListener>::State as StateChange>::Change;
+
+struct NotifierInner<L: StateChangeListener> {
+    listeners: Listeners<L>,
+    queue: Mutex<Queue<Change<L>>>,
+    is_draining: AtomicBool,
+    is_dispatched: AtomicBool,
+    is_throttling: AtomicBool,
+}
+
+struct Queue<C> {
+    version: StateVersion,
+    pending: VecDeque<(StateVersion, C)>,
+}
+
+impl<L: StateChangeListener + Clone> Notifier<L> {
+    pub fn new(listener: L) -> Self {
+        Self {
+            inner: Arc::new(NotifierInner {
+                listeners: Listeners::new(listener),
+                queue: Mutex::new(Queue {
+                    version: 0,
+                    pending: VecDeque::new(),
+                }),
+                is_draining: AtomicBool::new(false),
+                is_dispatched: AtomicBool::new(false),
+                is_throttling: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    pub fn listeners(&self) -> &Listeners<L> {
+        &self.inner.listeners
+    }
+
+    pub fn subscribe(&self, listener: L) -> Subscription {
+        self.inner.listeners.subscribe(listener)
+    }
+
+    /// The version of the most recently enqueued change.
+    pub fn version(&self) -> StateVersion {
+        self.inner
+            .queue
+            .lock()
+            .expect("Notifier::version failed to acquire lock")
+            .version
+    }
+
+    /// Assigns the next version to `change` and queues it for delivery, call
+    /// [`Notifier::deliver_pending`] to deliver it.
+    ///
+    /// Call this while still holding the lock guarding the state, so that
+    /// versions follow the order in which the state was changed.
+    pub fn enqueue(&self, change: Change<L>) -> StateVersion {
+        let mut queue = self
+            .inner
+            .queue
+            .lock()
+            .expect("Notifier::enqueue failed to acquire lock");
+        queue.version += 1;
+        let version = queue.version;
+        queue.pending.push_back((version, change));
+        version
+    }
+
+    /// Delivers all queued changes in version order, unless another thread is
+    /// already doing so, in which case that thread delivers them.
+    ///
+    /// With an installed dispatcher this schedules the delivery on the UI thread,
+    /// unless one is already scheduled.
+    pub fn deliver_pending(&self) {
+        self.deliver(false);
+    }
+
+    /// Like [`Notifier::deliver_pending`], but merges all queued changes into
+    /// one, used for changes whose delivery was delayed by a [`NotifyPolicy`].
+    pub fn deliver_pending_merged(&self) {
+        self.deliver(true);
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.inner.is_queue_empty()
+    }
+
+    /// Starts a throttling period, returns `false` if one is already running.
+    pub(crate) fn start_throttling(&self) -> bool {
+        !self.inner.is_throttling.swap(true, Ordering::AcqRel)
+    }
+
+    pub(crate) fn stop_throttling(&self) {
+        self.inner.is_throttling.store(false, Ordering::Release);
+    }
+
+    fn deliver(&self, merged: bool) {
+        let Some(dispatcher) = installed_dispatcher() else {
+            self.inner.drain(merged);
+            return;
+        };
+        if self.inner.is_dispatched.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let delivery = DispatchedDelivery {
+            inner: Arc::downgrade(&self.inner),
+        };
+        dispatcher.dispatch(Box::new(move || delivery.run()));
+    }
+}
+
+/// A delivery scheduled on the UI thread, the next change schedules another
+/// one once it ran, or if the dispatcher dropped it without running it.
+struct DispatchedDelivery<L: StateChangeListener> {
+    inner: Weak<NotifierInner<L>>,
+}
+
+impl<L: StateChangeListener + Clone> DispatchedDelivery<L> {
+    fn run(mut self) {
+        let Some(inner) = std::mem::take(&mut self.inner).upgrade() else {
+            return;
+        };
+        // Cleared before draining, so that a change enqueued during the
+        // delivery schedules another one.
+        inner.is_dispatched.store(false, Ordering::Release);
+        inner.drain(true);
+    }
+}
+
+impl<L: StateChangeListener> Drop for DispatchedDelivery<L> {
+    fn drop(&mut self) {
+        if let Some(inner) = self.inner.upgrade() {
+            debug!("Dispatched delivery dropped without running");
+            inner.is_dispatched.store(false, Ordering::Release);
+        }
+    }
+}
+
+// Not derived, which would require `L: Clone`.
+impl<L: StateChangeListener> Clone for Notifier<L> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<L: StateChangeListener + Clone> NotifierInner<L> {
+    fn drain(&self, conflate: bool) {
+        loop {
+            if self.is_draining.swap(true, Ordering::Acquire) {
+                return;
+            }
+            while let Some((version, change)) = self.pop(conflate) {
+                self.deliver(change, version);
+            }
+            self.is_draining.store(false, Ordering::Release);
+
+            // A change enqueued after the last `pop` but before the store above
+            // was left for us to deliver, take over again if so.
+            if self.is_queue_empty() {
+                return;
+            }
+        }
+    }
+
+    /// Pops the next change, or with `conflate` all pending changes merged into one.
+    fn pop(&self, conflate: bool) -> Option<(StateVersion, Change<L>)> {
+        let mut queue = self
+            .queue
+            .lock()
+            .expect("Notifier::pop failed to acquire lock");
+        let first = queue.pending.pop_front()?;
+        if !conflate {
+            return Some(first);
+        }
+        Some(
+            queue
+                .pending
+                .drain(..)
+                .fold(first, |(_, older), (version, newer)| {
+                    (version, L::State::merge(older, newer))
+                }),
+        )
+    }
+
+    fn is_queue_empty(&self) -> bool {
+        self.queue
+            .lock()
+            .expect("Notifier::is_queue_empty failed to acquire lock")
+            .pending
+            .is_empty()
+    }
+
+    fn deliver(&self, change: Change<L>, version: StateVersion) {
+        let listeners = self.listeners.snapshot();
+        debug!(
+            "Notifying {} listener(s) of state change (version {}): {:?}",
+            listeners.len(),
+            version,
+            change
+        );
+        for listener in listeners {
+            listener.on_state_change(change.clone(), version);
+        }
+    }
+}
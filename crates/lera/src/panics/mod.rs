@@ -0,0 +1,141 @@
+//! Panics caught in the exported methods of `#[lera::api(catch_unwind)]`.
+//!
+//! A caught panic is logged, reported to the [`PanicHook`] installed with
+//! [`install_panic_hook`], e.g. for crash reporting, and returned as
+//! `Err(E::from(panic))`, declare `E` with `#[lera::error]` to generate its
+//! `LeraPanic` variant.
+//!
+//! Every method must therefore return `Result<T, E>`, any other method, e.g.
+//! returning nothing, belongs in another `#[lera::api]` impl block:
+//!
+//! ```compile_fail
+//! # use std::sync::Arc;
+//! # uniffi::setup_scaffolding!();
+//! #
+//! #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+//! #[lera::state]
+//! pub struct WalletState {
+//!     pub balance: u32,
+//! }
+//!
+//! #[lera::model(state = WalletState)]
+//! pub struct Wallet {}
+//!
+//! #[lera::api(catch_unwind)]
+//! impl Wallet {
+//!     pub fn deposit(&self, amount: u32) {
+//!         lera::LeraModel::mutate(self, |state| state.balance += amount);
+//!     }
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! Declare `lera_setup_ffi_for_panics!()` to install the hook from Swift or Kotlin.
+
+use std::{
+    any::Any,
+    fmt,
+    future::Future,
+    panic::{AssertUnwindSafe, catch_unwind},
+    sync::{Arc, RwLock},
+};
+
+use futures::FutureExt;
+use log::error;
+
+/// A panic caught in an exported method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeraPanic {
+    /// The name of the method which panicked.
+    pub method: String,
+    /// The message the method panicked with.
+    pub message: String,
+}
+
+impl fmt::Display for LeraPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' panicked: {}", self.method, self.message)
+    }
+}
+
+impl std::error::Error for LeraPanic {}
+
+/// Reported to with every caught panic, installed with [`install_panic_hook`],
+/// or over FFI with `install_panic_hook` declared by `lera_setup_ffi_for_panics!`.
+pub trait PanicHook: Send + Sync {
+    fn on_panic(&self, panic: &LeraPanic);
+}
+
+static PANIC_HOOK: RwLock<Option<Arc<dyn PanicHook>>> = RwLock::new(None);
+
+/// Reports caught panics to `hook`, replacing any previously installed one.
+pub fn install_panic_hook(hook: Arc<dyn PanicHook>) {
+    *PANIC_HOOK.write().unwrap_or_else(|err| err.into_inner()) = Some(hook);
+}
+
+fn installed_panic_hook() -> Option<Arc<dyn PanicHook>> {
+    PANIC_HOOK
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .clone()
+}
+
+/// Runs the body of the exported `method`, catching and reporting a panic.
+#[doc(hidden)]
+pub fn catch<R>(method: &str, body: impl FnOnce() -> R) -> Result<R, LeraPanic> {
+    catch_unwind(AssertUnwindSafe(body)).map_err(|payload| report(method, payload))
+}
+
+/// Like [`catch`], for the body of an exported async `method`.
+#[doc(hidden)]
+pub async fn catch_async<R>(method: &str, body: impl Future<Output = R>) -> Result<R, LeraPanic> {
+    AssertUnwindSafe(body)
+        .catch_unwind()
+        .await
+        .map_err(|payload| report(method, payload))
+}
+
+fn report(method: &str, payload: Box<dyn Any + Send>) -> LeraPanic {
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        (*message).to_owned()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".to_owned()
+    };
+    let panic = LeraPanic {
+        method: method.to_owned(),
+        message,
+    };
+    error!("Caught panic: {}", panic);
+    if let Some(hook) = installed_panic_hook() {
+        hook.on_panic(&panic);
+    }
+    panic
+}
+
+#[macro_export]
+macro_rules! lera_setup_ffi_for_panics {
+    () => {
+        /// Panic hook trait that the foreign code implements, e.g. to report
+        /// panics caught in `#[lera::api(catch_unwind)]` methods to crash reporting.
+        #[uniffi::export(with_foreign)]
+        pub trait FfiPanicHook: Send + Sync {
+            fn on_panic(&self, method: String, message: String);
+        }
+
+        #[uniffi::export]
+        pub fn install_panic_hook(hook: std::sync::Arc<dyn FfiPanicHook>) {
+            struct Bridge {
+                inner: std::sync::Arc<dyn FfiPanicHook>,
+            }
+            impl ::lera::panics::PanicHook for Bridge {
+                fn on_panic(&self, panic: &::lera::panics::LeraPanic) {
+                    self.inner
+                        .on_panic(panic.method.clone(), panic.message.clone())
+                }
+            }
+            ::lera::panics::install_panic_hook(std::sync::Arc::new(Bridge { inner: hook }));
+        }
+    };
+}
@@ -0,0 +1,296 @@
+//! Saving and restoring the state of models declared with
+//! `#[lera::state(persist = "key")]`.
+//!
+//! The state is serialized to JSON after every state change and restored in
+//! `LeraModel::new`, through the [`Storage`] installed with [`install_storage`],
+//! or over FFI with `install_state_storage` declared by
+//! `lera_setup_ffi_for_persistence!`. Without one nothing is saved or restored
+//! and an error is logged once, tests and tools can install a [`FileStorage`].
+//!
+//! See [`PersistedState`] for how states saved by older versions are migrated.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, RwLock,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use log::{error, warn};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+
+/// Implemented by `#[lera::state]`, saving and restoring the state when it is
+/// declared with `persist = "key"` and doing nothing otherwise.
+pub trait Persistence: Sized {
+    /// Whether [`Persistence::save`] does anything, so that models of states
+    /// which are not persisted skip it entirely.
+    const IS_PERSISTED: bool = false;
+
+    /// Called with the latest state after state changes, once the model is no
+    /// longer locked for mutations. Intermediate states may be skipped.
+    fn save(&self) {}
+
+    /// Called in `LeraModel::new` with the initial state, returns the saved state
+    /// if there is one.
+    fn restore(self) -> Self {
+        self
+    }
+}
+
+/// Implemented by `#[lera::state(persist = "key", version = N)]`.
+///
+/// States are saved as `{"lera.version": N, "state": ...}`. When a state saved with
+/// an older version is restored, it is passed through the migrations from its
+/// version up to `VERSION`, which `#[lera::state]` collects from the functions
+/// `migrate_v1_to_v2`, `migrate_v2_to_v3`, ... next to the state. Data saved
+/// without a version is version 1.
+pub trait PersistedState: Serialize + DeserializeOwned {
+    const KEY: &'static str;
+    const VERSION: u32;
+    /// `MIGRATIONS[i]` migrates version `i + 1` to version `i + 2`.
+    const MIGRATIONS: &'static [Migration];
+}
+
+/// Migrates the JSON of a state from one version to the next.
+pub type Migration = fn(Value) -> Result<Value, String>;
+
+/// What a `migrate_vN_to_vM` function returns, the migrated JSON or an error.
+pub trait MigrationOutput {
+    fn into_migrated(self) -> Result<Value, String>;
+}
+
+impl MigrationOutput for Value {
+    fn into_migrated(self) -> Result<Value, String> {
+        Ok(self)
+    }
+}
+
+impl<E: std::fmt::Display> MigrationOutput for Result<Value, E> {
+    fn into_migrated(self) -> Result<Value, String> {
+        self.map_err(|err| err.to_string())
+    }
+}
+
+/// Stores the serialized states of models, keyed by their `persist` key.
+pub trait Storage: Send + Sync {
+    fn load(&self, key: &str) -> Option<String>;
+    fn save(&self, key: &str, value: String);
+}
+
+static STORAGE: RwLock<Option<Arc<dyn Storage>>> = RwLock::new(None);
+
+/// Makes models save their states in `storage`, replacing any previously
+/// installed one. Install it before creating any persisted model.
+pub fn install_storage(storage: Arc<dyn Storage>) {
+    *STORAGE.write().expect("STORAGE poisoned") = Some(storage);
+}
+
+/// Whether the missing storage was reported, which is done once rather than
+/// with every save.
+static REPORTED_NO_STORAGE: AtomicBool = AtomicBool::new(false);
+
+fn storage(key: &str) -> Option<Arc<dyn Storage>> {
+    let storage = STORAGE.read().expect("STORAGE poisoned").clone();
+    if storage.is_none() && !REPORTED_NO_STORAGE.swap(true, Ordering::Relaxed) {
+        error!(
+            "No storage installed, persisted states, e.g. '{}', are neither saved nor restored",
+            key
+        );
+    }
+    storage
+}
+
+/// Saves `state` in the installed [`Storage`], logging failures.
+pub fn save<S: PersistedState>(state: &S) {
+    let Some(storage) = storage(S::KEY) else {
+        return;
+    };
+    match encode(state) {
+        Ok(json) => storage.save(S::KEY, json),
+        Err(err) => warn!("Failed to serialize state '{}': {}", S::KEY, err),
+    }
+}
+
+/// The saved state, migrated to the current version, or `initial` if there is
+/// none or it cannot be restored.
+pub fn restore<S: PersistedState>(initial: S) -> S {
+    let Some(json) = storage(S::KEY).and_then(|storage| storage.load(S::KEY)) else {
+        return initial;
+    };
+    decode(&json).unwrap_or_else(|err| {
+        warn!(
+            "Failed to restore state '{}', using initial state: {}",
+            S::KEY,
+            err
+        );
+        initial
+    })
+}
+
+/// Serializes `state` with its version, as it is saved.
+pub fn encode<S: PersistedState>(state: &S) -> serde_json::Result<String> {
+    serde_json::to_string(&Saved {
+        version: S::VERSION,
+        state,
+    })
+}
+
+/// Deserializes a saved state, migrating it from the version it was saved with.
+/// JSON without the version tag is the state itself, saved as version 1.
+pub fn decode<S: PersistedState>(json: &str) -> Result<S, String> {
+    let value: Value = serde_json::from_str(json).map_err(|err| err.to_string())?;
+    let (version, state) = match value {
+        Value::Object(mut object) if object.contains_key(VERSION) => {
+            let version = object[VERSION]
+                .as_u64()
+                .ok_or_else(|| format!("'{}' is not a version", VERSION))?;
+            let state = object
+                .remove(STATE)
+                .ok_or_else(|| format!("missing '{}'", STATE))?;
+            (version, state)
+        }
+        unversioned => (1, unversioned),
+    };
+    migrate(state, version)
+}
+
+/// Migrates the JSON of a state saved with `version` to the current version and
+/// deserializes it.
+pub fn migrate<S: PersistedState>(mut state: Value, version: u64) -> Result<S, String> {
+    if version == 0 || version > u64::from(S::VERSION) {
+        return Err(format!(
+            "cannot migrate version {} to version {}",
+            version,
+            S::VERSION
+        ));
+    }
+    for (from, migration) in (version..).zip(&S::MIGRATIONS[(version - 1) as usize..]) {
+        state = migration(state)
+            .map_err(|err| format!("migrating v{} to v{}: {}", from, from + 1, err))?;
+    }
+    serde_json::from_value(state).map_err(|err| err.to_string())
+}
+
+/// Test helper asserting that a fixture exists for every version of `S` and
+/// that each migrates to the current version and survives being saved and
+/// restored.
+///
+/// Fixtures are the JSON of the state at each version, without the version,
+/// in files `v1.json`, `v2.json`, ... in `directory`. Add one whenever
+/// bumping the version, e.g. by copying a state saved by the previous release.
+pub fn assert_fixtures_round_trip<S>(directory: impl AsRef<Path>) -> Vec<S>
+where
+    S: PersistedState + PartialEq + std::fmt::Debug,
+{
+    let directory = directory.as_ref();
+    (1..=S::VERSION)
+        .map(|version| {
+            let path = directory.join(format!("v{}.json", version));
+            let fixture = fs::read_to_string(&path)
+                .unwrap_or_else(|err| panic!("missing fixture {}: {}", path.display(), err));
+            let fixture: Value = serde_json::from_str(&fixture)
+                .unwrap_or_else(|err| panic!("invalid fixture {}: {}", path.display(), err));
+            let state: S = migrate(fixture, u64::from(version))
+                .unwrap_or_else(|err| panic!("fixture {}: {}", path.display(), err));
+            let saved = encode(&state).expect("state must serialize");
+            let restored: S = decode(&saved).expect("saved state must restore");
+            assert_eq!(
+                restored,
+                state,
+                "fixture {} changed when saved and restored",
+                path.display()
+            );
+            state
+        })
+        .collect()
+}
+
+/// How states are saved, data without the version tag is version 1.
+#[derive(Serialize)]
+struct Saved<'a, S> {
+    #[serde(rename = "lera.version")]
+    version: u32,
+    state: &'a S,
+}
+
+/// Tags saved states, unlikely to be a field of a state saved without it.
+const VERSION: &str = "lera.version";
+const STATE: &str = "state";
+
+/// Stores every state in its own `{key}.json` file in a directory, meant for
+/// tests and command line tools, apps install a platform storage instead.
+#[derive(Debug, Clone)]
+pub struct FileStorage {
+    directory: PathBuf,
+}
+
+impl FileStorage {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.directory.join(format!("{}.json", key))
+    }
+
+    fn write(&self, key: &str, value: &str) -> io::Result<()> {
+        fs::create_dir_all(&self.directory)?;
+        // Written next to the target and renamed, so a crash never leaves half a state.
+        let tmp = self.directory.join(format!("{}.json.tmp", key));
+        fs::write(&tmp, value)?;
+        fs::rename(tmp, self.path(key))
+    }
+}
+
+impl Storage for FileStorage {
+    fn load(&self, key: &str) -> Option<String> {
+        match fs::read_to_string(self.path(key)) {
+            Ok(value) => Some(value),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => {
+                warn!("Failed to read state '{}': {}", key, err);
+                None
+            }
+        }
+    }
+
+    fn save(&self, key: &str, value: String) {
+        if let Err(err) = self.write(key, &value) {
+            warn!("Failed to write state '{}': {}", key, err);
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! lera_setup_ffi_for_persistence {
+    () => {
+        /// Storage trait that the foreign code implements, e.g. using `UserDefaults`
+        /// or `DataStore`, saving the JSON of persisted states under their key.
+        #[uniffi::export(with_foreign)]
+        pub trait StateStorage: Send + Sync {
+            fn load(&self, key: String) -> Option<String>;
+            fn save(&self, key: String, value: String);
+        }
+
+        #[uniffi::export]
+        pub fn install_state_storage(storage: std::sync::Arc<dyn StateStorage>) {
+            struct Bridge {
+                inner: std::sync::Arc<dyn StateStorage>,
+            }
+            impl ::lera::persistence::Storage for Bridge {
+                fn load(&self, key: &str) -> Option<String> {
+                    self.inner.load(key.to_owned())
+                }
+                fn save(&self, key: &str, value: String) {
+                    self.inner.save(key.to_owned(), value)
+                }
+            }
+            ::lera::persistence::install_storage(std::sync::Arc::new(Bridge { inner: storage }));
+        }
+    };
+}
@@ -0,0 +1,93 @@
+use std::{any::type_name, fmt, sync::PoisonError};
+
+use log::error;
+
+use crate::{LeraModel, StateWriter, commit, did_commit};
+
+/// What a model does with its state once a panic while mutating it poisoned
+/// its store, set with `#[lera::model(state = S, poison = reset)]`.
+///
+/// A panicking mutation never commits, so the state is the one before it.
+/// Either way the poisoning is logged and the store is usable again, so one
+/// panic does not make every later call panic across the FFI boundary.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PoisonPolicy {
+    /// Keeps the state before the panicking mutation.
+    #[default]
+    Recover,
+    /// Replaces the state with its `Default`, notifying listeners of it.
+    Reset,
+}
+
+/// Errors of the fallible [`LeraModel`] methods, e.g. [`LeraModel::try_access`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LeraError {
+    /// A panic while mutating the state poisoned its store, the [`PoisonPolicy`]
+    /// of the model is applied by the next infallible mutation.
+    StatePoisoned {
+        /// The type name of the model.
+        model: String,
+    },
+}
+
+impl LeraError {
+    pub(crate) fn state_poisoned<M: ?Sized>() -> Self {
+        Self::StatePoisoned {
+            model: type_name::<M>().to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for LeraError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::StatePoisoned { model } => {
+                write!(f, "the state of '{model}' is poisoned by a panic")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LeraError {}
+
+/// Exclusive access to commit the next state of `model`, applying its
+/// [`PoisonPolicy`] first if the store is poisoned.
+pub(crate) fn write_state<M: LeraModel + ?Sized>(model: &M) -> StateWriter<'_, M::State> {
+    let store = model.get_store();
+    if store.is_poisoned() {
+        recover(model);
+    }
+    // Poisoned again since recovering, the policy is applied on the next mutation.
+    store.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Applies the [`PoisonPolicy`] of `model` and clears the poisoning.
+fn recover<M: LeraModel + ?Sized>(model: &M) {
+    let store = model.get_store();
+    {
+        let writer = store.lock().unwrap_or_else(PoisonError::into_inner);
+        if !store.is_poisoned() {
+            // Recovered by another thread while waiting for the lock.
+            return;
+        }
+        match model.poison_policy() {
+            PoisonPolicy::Recover => {
+                error!(
+                    "State of '{}' poisoned by a panic, keeping the state before it",
+                    type_name::<M>()
+                );
+            }
+            PoisonPolicy::Reset => {
+                error!(
+                    "State of '{}' poisoned by a panic, resetting it to its default",
+                    type_name::<M>()
+                );
+                let current = writer.current();
+                commit(model, &writer, &current, M::State::default());
+            }
+        }
+        store.clear_poison();
+    }
+
+    did_commit(model);
+}
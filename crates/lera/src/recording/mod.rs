@@ -0,0 +1,167 @@
+//! Recording the state changes of a model, e.g. to attach them to a bug report
+//! and replay them in a test, enabled with `#[lera::model(state = S, recorder)]`.
+//!
+//! Every recorded change has the name of the `#[lera::api]` method which caused
+//! it, `None` for changes made outside of one, e.g. by a background task.
+
+use std::{
+    cell::Cell,
+    future::Future,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::error;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+/// A state change recorded by a [`Recorder`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedChange<S> {
+    /// Milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    /// The `#[lera::api]` method which made the change.
+    pub method: Option<String>,
+    pub before: S,
+    pub after: S,
+}
+
+/// The state changes recorded between `start_recording` and `stop_recording`,
+/// in the order they were made.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recording<S> {
+    pub changes: Vec<RecordedChange<S>>,
+}
+
+impl<S> Default for Recording<S> {
+    fn default() -> Self {
+        Self {
+            changes: Vec::new(),
+        }
+    }
+}
+
+impl<S> Recording<S> {
+    /// The state before the first recorded change.
+    pub fn initial_state(&self) -> Option<&S> {
+        self.changes.first().map(|change| &change.before)
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String>
+    where
+        S: Serialize,
+    {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self>
+    where
+        S: DeserializeOwned,
+    {
+        serde_json::from_str(json)
+    }
+}
+
+/// Used by `#[lera::model(recorder)]` to export recordings, an empty string if
+/// the recording cannot be serialized.
+pub fn export_json<S: Serialize>(recording: &Recording<S>) -> String {
+    recording.to_json().unwrap_or_else(|err| {
+        error!("Failed to serialize recording: {}", err);
+        String::new()
+    })
+}
+
+/// Records the state changes of a model while recording.
+pub struct Recorder<S> {
+    changes: Mutex<Option<Vec<RecordedChange<S>>>>,
+}
+
+impl<S> Default for Recorder<S> {
+    fn default() -> Self {
+        Self {
+            changes: Mutex::new(None),
+        }
+    }
+}
+
+impl<S: Clone> Recorder<S> {
+    /// Starts a new recording, discarding any unfinished one.
+    pub fn start(&self) {
+        *self.lock() = Some(Vec::new());
+    }
+
+    /// Stops recording and returns what was recorded.
+    pub fn stop(&self) -> Recording<S> {
+        Recording {
+            changes: self.lock().take().unwrap_or_default(),
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.lock().is_some()
+    }
+
+    /// Records the change from `before` to `after` if recording.
+    pub fn record(&self, before: &S, after: &S) {
+        let mut changes = self.lock();
+        let Some(changes) = changes.as_mut() else {
+            return;
+        };
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+        changes.push(RecordedChange {
+            timestamp_ms,
+            method: current_api_method().map(str::to_owned),
+            before: before.clone(),
+            after: after.clone(),
+        });
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Vec<RecordedChange<S>>>> {
+        self.changes
+            .lock()
+            .expect("Recorder failed to acquire lock")
+    }
+}
+
+thread_local! {
+    static API_METHOD: Cell<Option<&'static str>> = const { Cell::new(None) };
+}
+
+/// The outermost `#[lera::api]` method running on this thread.
+pub fn current_api_method() -> Option<&'static str> {
+    API_METHOD.get()
+}
+
+/// Marks the current thread as running the `#[lera::api]` method `name` until
+/// dropped, used by the code generated by `#[lera::api]`.
+#[doc(hidden)]
+pub struct ApiMethod {
+    previous: Option<&'static str>,
+}
+
+impl ApiMethod {
+    pub fn enter(name: &'static str) -> Self {
+        let previous = API_METHOD.get();
+        // An api method calling another is attributed to the outer one.
+        API_METHOD.set(previous.or(Some(name)));
+        Self { previous }
+    }
+}
+
+impl Drop for ApiMethod {
+    fn drop(&mut self) {
+        API_METHOD.set(self.previous);
+    }
+}
+
+/// Runs `future` as the async `#[lera::api]` method `name`, on whichever
+/// thread polls it.
+#[doc(hidden)]
+pub fn in_api_method<F: Future>(name: &'static str, future: F) -> impl Future<Output = F::Output> {
+    let mut future = Box::pin(future);
+    futures::future::poll_fn(move |cx| {
+        let _api_method = ApiMethod::enter(name);
+        future.as_mut().poll(cx)
+    })
+}
@@ -0,0 +1,29 @@
+//! Unidirectional data flow: the state of a model only changes by reducing the
+//! `#[lera::actions]` sent to it.
+//!
+//! Declared with `#[lera::model(state = S, actions = MyAction)]`, which exports
+//! `send_action` for the generated ViewModels to forward `send(action)` to.
+
+use std::sync::Arc;
+
+use crate::{Effect, LeraModel, effect};
+
+/// A model whose state changes by reducing actions, all of its logic being the
+/// pure function [`Reducer::reduce`] which can be tested without a model.
+pub trait Reducer: LeraModel {
+    /// The `#[lera::actions]` enum of the model.
+    type Action: Send + 'static;
+
+    /// Applies `action` to `state`, returns what to do next.
+    fn reduce(state: &mut Self::State, action: Self::Action) -> Effect<Self::Action>;
+
+    /// Reduces `action` as one mutation, then runs its effect, sending the
+    /// actions it feeds back.
+    fn send(self: &Arc<Self>, action: Self::Action)
+    where
+        Self: Sized + Send + Sync + 'static,
+    {
+        let effect = self.mutate(|state| Self::reduce(state, action));
+        effect::execute(self, effect, |model, action| model.send(action));
+    }
+}
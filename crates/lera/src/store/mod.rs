@@ -0,0 +1,138 @@
+use std::{
+    ops::Deref,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use arc_swap::ArcSwap;
+
+use crate::{StateVersion, persistence::Persistence};
+
+/// An immutable state of a model, as committed by one of its mutations.
+#[derive(Debug)]
+pub struct Snapshot<S> {
+    version: StateVersion,
+    state: S,
+}
+
+impl<S> Snapshot<S> {
+    /// The number of changes committed before this state, `0` for the state
+    /// the model was created with.
+    pub fn version(&self) -> StateVersion {
+        self.version
+    }
+
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+}
+
+impl<S: Clone> Snapshot<S> {
+    /// The state, cloned only if the snapshot is still shared.
+    pub(crate) fn into_state(snapshot: Arc<Self>) -> S {
+        Arc::try_unwrap(snapshot)
+            .map(|snapshot| snapshot.state)
+            .unwrap_or_else(|snapshot| snapshot.state.clone())
+    }
+}
+
+impl<S> Deref for Snapshot<S> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        &self.state
+    }
+}
+
+/// Holds the state of a model as [`Snapshot`]s, which are read without locking.
+///
+/// Mutations work on a copy of the latest snapshot and commit it as the next
+/// one, one at a time. A panic while mutating commits nothing, but poisons the
+/// store, see [`crate::PoisonPolicy`].
+pub struct StateStore<S> {
+    snapshot: ArcSwap<Snapshot<S>>,
+    writer: Mutex<()>,
+    /// The version of the latest saved snapshot, see [`StateStore::save_latest`].
+    saved: Mutex<StateVersion>,
+}
+
+impl<S> StateStore<S> {
+    pub fn new(state: S) -> Self {
+        Self {
+            snapshot: ArcSwap::from_pointee(Snapshot { version: 0, state }),
+            writer: Mutex::new(()),
+            saved: Mutex::new(0),
+        }
+    }
+
+    /// The latest snapshot.
+    pub fn load(&self) -> Arc<Snapshot<S>> {
+        self.snapshot.load_full()
+    }
+
+    /// Calls `read` with the latest state, without cloning it.
+    pub fn read<R>(&self, read: impl FnOnce(&S) -> R) -> R {
+        read(&self.snapshot.load())
+    }
+
+    /// Whether a panic while mutating poisoned the store.
+    pub fn is_poisoned(&self) -> bool {
+        self.writer.is_poisoned()
+    }
+
+    /// Waits for other mutations to commit, then gives exclusive access to
+    /// commit the next snapshot.
+    pub(crate) fn lock(&self) -> Result<StateWriter<'_, S>, PoisonError<StateWriter<'_, S>>> {
+        let writer = |guard| StateWriter {
+            store: self,
+            _guard: guard,
+        };
+        self.writer
+            .lock()
+            .map(writer)
+            .map_err(|err| PoisonError::new(writer(err.into_inner())))
+    }
+
+    pub(crate) fn clear_poison(&self) {
+        self.writer.clear_poison();
+    }
+
+    /// Saves the latest snapshot unless it already was, called after committing
+    /// without holding the write lock, so that saving never blocks mutations.
+    /// Saves happen one at a time and never replace a newer state with an older.
+    pub(crate) fn save_latest(&self)
+    where
+        S: Persistence,
+    {
+        if !S::IS_PERSISTED {
+            return;
+        }
+        let mut saved = self.saved.lock().unwrap_or_else(PoisonError::into_inner);
+        let latest = self.load();
+        if latest.version <= *saved {
+            return;
+        }
+        latest.save();
+        *saved = latest.version;
+    }
+}
+
+/// Exclusive access to commit the next snapshot of a [`StateStore`].
+pub(crate) struct StateWriter<'a, S> {
+    store: &'a StateStore<S>,
+    _guard: MutexGuard<'a, ()>,
+}
+
+impl<S> StateWriter<'_, S> {
+    /// The latest snapshot, which nothing else commits to while locked.
+    pub(crate) fn current(&self) -> Arc<Snapshot<S>> {
+        self.store.load()
+    }
+
+    /// Replaces the latest snapshot with `state`, returns the new snapshot.
+    pub(crate) fn commit(&self, state: S) -> Arc<Snapshot<S>> {
+        let version = self.store.snapshot.load().version + 1;
+        let snapshot = Arc::new(Snapshot { version, state });
+        self.store.snapshot.store(Arc::clone(&snapshot));
+        snapshot
+    }
+}
@@ -0,0 +1,83 @@
+//! Streams returned by `#[lera::api]` methods, e.g. live search results or the
+//! progress of a download, delivered without copying them into the state.
+//!
+//! A method returning `impl Stream<Item = T>` or a [`Stream<T>`] exports a
+//! `{Model}{Method}Stream` object instead, whose async `next` the generated
+//! ViewModels iterate as an `AsyncThrowingStream` in Swift and a `Flow` in
+//! Kotlin. Items `Result<T, E>` are thrown as `E`. Cancelling the iteration
+//! calls `cancel`, dropping the Rust stream.
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{
+    StreamExt,
+    lock::Mutex,
+    stream::{AbortHandle, Abortable, BoxStream},
+};
+
+use crate::executor::with_context;
+
+/// A boxed stream of `T`, for `#[lera::api]` methods which cannot name the
+/// type of the stream they return, e.g. returning different streams per branch.
+pub struct Stream<T>(BoxStream<'static, T>);
+
+impl<T> Stream<T> {
+    pub fn new(stream: impl futures::Stream<Item = T> + Send + 'static) -> Self {
+        Self(stream.boxed())
+    }
+}
+
+impl<T> futures::Stream for Stream<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.0.poll_next_unpin(cx)
+    }
+}
+
+/// The Rust side of an exported stream, held by the generated
+/// `{Model}{Method}Stream` object.
+pub struct StreamHandle<T> {
+    stream: Mutex<Option<Abortable<BoxStream<'static, T>>>>,
+    abort: AbortHandle,
+}
+
+impl<T: Send + 'static> StreamHandle<T> {
+    pub fn new(stream: impl futures::Stream<Item = T> + Send + 'static) -> Self {
+        let (stream, abort) = futures::stream::abortable(stream.boxed());
+        Self {
+            stream: Mutex::new(Some(stream)),
+            abort,
+        }
+    }
+
+    /// The next item, polled with the context of the executor entered, or
+    /// `None` once the stream ended or was cancelled.
+    pub async fn next(&self) -> Option<T> {
+        let mut stream = self.stream.lock().await;
+        let item = match stream.as_mut() {
+            Some(stream) => with_context(stream.next()).await,
+            None => None,
+        };
+        if item.is_none() {
+            // Drops the stream, and whatever it holds, as soon as it ended.
+            *stream = None;
+        }
+        item
+    }
+
+    /// Ends the stream, a pending `next` returning `None` right away.
+    pub fn cancel(&self) {
+        self.abort.abort();
+        if let Some(mut stream) = self.stream.try_lock() {
+            *stream = None;
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.abort.is_aborted()
+    }
+}
@@ -0,0 +1,144 @@
+use std::sync::{
+    Arc, Mutex, RwLock, Weak,
+    atomic::{AtomicU64, Ordering},
+};
+
+type SubscriptionId = u64;
+
+/// All listeners subscribed to the state changes of a model.
+///
+/// The listener passed to `LeraModel::new` stays subscribed for the lifetime of the
+/// model, listeners added with [`Listeners::subscribe`] stay subscribed until their
+/// [`Subscription`] is cancelled or dropped.
+pub struct Listeners<L> {
+    inner: Arc<ListenersInner<L>>,
+}
+
+struct ListenersInner<L> {
+    next_id: AtomicU64,
+    entries: RwLock<Vec<(SubscriptionId, L)>>,
+}
+
+impl<L> Default for Listeners<L> {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(ListenersInner {
+                next_id: AtomicU64::new(0),
+                entries: RwLock::new(Vec::new()),
+            }),
+        }
+    }
+}
+
+impl<L: Clone + Send + Sync + 'static> Listeners<L> {
+    pub fn new(listener: L) -> Self {
+        let listeners = Self::default();
+        listeners.insert(listener);
+        listeners
+    }
+
+    fn insert(&self, listener: L) -> SubscriptionId {
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        self.inner
+            .entries
+            .write()
+            .expect("Listeners::insert failed to acquire write lock")
+            .push((id, listener));
+        id
+    }
+
+    /// Adds `listener`, which stays subscribed until the returned [`Subscription`]
+    /// is cancelled or dropped.
+    pub fn subscribe(&self, listener: L) -> Subscription {
+        let id = self.insert(listener);
+        let registry: Arc<dyn Unsubscribe> = self.inner.clone();
+        Subscription {
+            id,
+            registry: Mutex::new(Some(Arc::downgrade(&registry))),
+        }
+    }
+
+    /// The currently subscribed listeners, cloned so that they can be called
+    /// without holding any lock, allowing listeners to (un)subscribe.
+    pub fn snapshot(&self) -> Vec<L> {
+        self.inner
+            .entries
+            .read()
+            .expect("Listeners::snapshot failed to acquire read lock")
+            .iter()
+            .map(|(_, listener)| listener.clone())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner
+            .entries
+            .read()
+            .expect("Listeners::len failed to acquire read lock")
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+trait Unsubscribe: Send + Sync {
+    fn unsubscribe(&self, id: SubscriptionId);
+}
+
+impl<L: Send + Sync> Unsubscribe for ListenersInner<L> {
+    fn unsubscribe(&self, id: SubscriptionId) {
+        self.entries
+            .write()
+            .expect("Listeners::unsubscribe failed to acquire write lock")
+            .retain(|(existing, _)| *existing != id);
+    }
+}
+
+/// Keeps a listener subscribed, see [`Listeners::subscribe`].
+///
+/// Dropping the subscription, or calling [`Subscription::cancel`], detaches the
+/// listener. A subscription does not keep the model alive.
+pub struct Subscription {
+    id: SubscriptionId,
+    registry: Mutex<Option<Weak<dyn Unsubscribe>>>,
+}
+
+impl Subscription {
+    /// Detaches the listener, calling this more than once has no effect.
+    pub fn cancel(&self) {
+        let registry = self
+            .registry
+            .lock()
+            .expect("Subscription::cancel failed to acquire lock")
+            .take();
+        if let Some(registry) = registry.and_then(|weak| weak.upgrade()) {
+            registry.unsubscribe(self.id);
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.registry
+            .lock()
+            .expect("Subscription::is_cancelled failed to acquire lock")
+            .as_ref()
+            .and_then(|weak| weak.upgrade())
+            .is_none()
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+impl std::fmt::Debug for Subscription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Subscription")
+            .field("id", &self.id)
+            .field("is_cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
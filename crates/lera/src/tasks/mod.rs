@@ -0,0 +1,291 @@
+//! Background work bound to the lifetime of a model.
+//!
+//! `#[lera::model]` gives every model a [`Tasks`] registry, used by
+//! `LeraModel::spawn`, `LeraModel::spawn_keyed` and `LeraModel::spawn_interval`.
+//! Tasks only hold the model weakly and are cancelled when the model is dropped.
+//! They run on the [`executor`](crate::executor) chosen for the crate, unless
+//! a test intercepts them with [`Tasks::intercept`] to run them itself.
+
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fmt,
+    future::Future,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    task::{Poll, Waker},
+    time::{Duration, Instant},
+};
+
+use futures::{
+    StreamExt,
+    future::{AbortHandle, abortable},
+    stream::FuturesUnordered,
+};
+use log::debug;
+
+use crate::executor::{BoxFuture, executor, with_context};
+
+/// Whether an interval task should keep ticking.
+pub type ShouldContinue = bool;
+
+/// Identifies a task of a model, spawning a task with the key of a running
+/// task cancels the running one.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct TaskKey(Repr);
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Repr {
+    Named(Cow<'static, str>),
+    Anonymous(u64),
+}
+
+impl From<&'static str> for TaskKey {
+    fn from(value: &'static str) -> Self {
+        Self(Repr::Named(Cow::Borrowed(value)))
+    }
+}
+
+impl From<String> for TaskKey {
+    fn from(value: String) -> Self {
+        Self(Repr::Named(Cow::Owned(value)))
+    }
+}
+
+impl fmt::Debug for TaskKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Repr::Named(name) => write!(f, "{:?}", name),
+            Repr::Anonymous(id) => write!(f, "#{}", id),
+        }
+    }
+}
+
+/// The running tasks of a model, all of them are cancelled on drop.
+#[derive(Default)]
+pub struct Tasks {
+    next_anonymous_id: AtomicU64,
+    handles: Mutex<HashMap<TaskKey, TaskHandle>>,
+    /// `Some` once [`Tasks::intercept`] is called.
+    intercepted: Mutex<Option<Intercepted>>,
+}
+
+/// The tasks spawned while intercepting, not yet run.
+#[derive(Default)]
+struct Intercepted {
+    queue: Vec<(TaskKey, AbortHandle, BoxFuture)>,
+    /// Wakes [`Tasks::run_intercepted`] when a task it runs spawns another.
+    waker: Option<Waker>,
+}
+
+struct TaskHandle {
+    abort: AbortHandle,
+    is_finished: Arc<AtomicBool>,
+}
+
+impl TaskHandle {
+    fn is_finished(&self) -> bool {
+        self.is_finished.load(Ordering::Acquire) || self.abort.is_aborted()
+    }
+
+    fn abort(&self) {
+        self.abort.abort();
+    }
+}
+
+impl Tasks {
+    pub(crate) fn anonymous_key(&self) -> TaskKey {
+        TaskKey(Repr::Anonymous(
+            self.next_anonymous_id.fetch_add(1, Ordering::Relaxed),
+        ))
+    }
+
+    /// Runs `future` on the executor, cancelling any task running with `key`.
+    pub fn spawn(&self, key: TaskKey, future: impl Future<Output = ()> + Send + 'static) {
+        let (future, abort) = abortable(future);
+        let is_finished = Arc::new(AtomicBool::new(false));
+        let handle = TaskHandle {
+            abort: abort.clone(),
+            is_finished: is_finished.clone(),
+        };
+        let task: BoxFuture = Box::pin(async move {
+            let _ = future.await;
+            is_finished.store(true, Ordering::Release);
+        });
+        // Cancelled before the new task starts, so that the two never run at
+        // the same time.
+        let previous = {
+            let mut handles = self
+                .handles
+                .lock()
+                .expect("Tasks::spawn failed to acquire lock");
+            handles.retain(|_, handle| !handle.is_finished());
+            handles.insert(key.clone(), handle)
+        };
+        if let Some(previous) = previous {
+            debug!("Cancelling task {:?}, replaced by a new one", key);
+            previous.abort();
+        }
+        let task = match self
+            .intercepted
+            .lock()
+            .expect("Tasks::spawn failed to acquire lock")
+            .as_mut()
+        {
+            Some(intercepted) => {
+                intercepted.queue.push((key, abort, task));
+                if let Some(waker) = intercepted.waker.take() {
+                    waker.wake();
+                }
+                None
+            }
+            None => Some(task),
+        };
+        if let Some(task) = task {
+            executor().spawn(task);
+        }
+    }
+
+    /// Runs `tick` every `period`, the first time after one `period`, until it
+    /// returns `false` or the task is cancelled.
+    pub fn spawn_interval(
+        &self,
+        key: TaskKey,
+        period: Duration,
+        mut tick: impl FnMut() -> ShouldContinue + Send + 'static,
+    ) {
+        self.spawn(key, async move {
+            let executor = executor();
+            let mut deadline = Instant::now();
+            loop {
+                // Ticks at fixed times, not drifting by the time `tick` takes.
+                deadline += period;
+                let delay = deadline.saturating_duration_since(Instant::now());
+                executor.sleep(delay).await;
+                if !tick() {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Cancels the task running with `key`, returns whether there was one.
+    pub fn cancel(&self, key: &TaskKey) -> bool {
+        let handle = self
+            .handles
+            .lock()
+            .expect("Tasks::cancel failed to acquire lock")
+            .remove(key);
+        handle
+            .map(|handle| {
+                let was_running = !handle.is_finished();
+                handle.abort();
+                was_running
+            })
+            .unwrap_or(false)
+    }
+
+    pub fn is_running(&self, key: &TaskKey) -> bool {
+        self.handles
+            .lock()
+            .expect("Tasks::is_running failed to acquire lock")
+            .get(key)
+            .is_some_and(|handle| !handle.is_finished())
+    }
+
+    /// Queues the tasks spawned from now on instead of running them, for a test
+    /// to assert on them and run them with [`Tasks::run_intercepted`].
+    pub fn intercept(&self) {
+        self.intercepted
+            .lock()
+            .expect("Tasks::intercept failed to acquire lock")
+            .get_or_insert_with(Intercepted::default);
+    }
+
+    /// The keys of the intercepted tasks not yet run, nor cancelled, in the
+    /// order they were spawned.
+    pub fn intercepted(&self) -> Vec<TaskKey> {
+        self.intercepted
+            .lock()
+            .expect("Tasks::intercepted failed to acquire lock")
+            .iter()
+            .flat_map(|intercepted| &intercepted.queue)
+            .filter(|(_, abort, _)| !abort.is_aborted())
+            .map(|(key, _, _)| key.clone())
+            .collect()
+    }
+
+    /// Runs the intercepted tasks on the current thread, and the tasks they
+    /// spawn, until all of them completed. Returns how many ran.
+    pub fn run_intercepted(&self) -> usize {
+        let mut running = FuturesUnordered::new();
+        let mut completed = 0;
+        let run_all = std::future::poll_fn(|cx| {
+            loop {
+                running.extend(self.take_intercepted(cx.waker()));
+                if running.is_empty() {
+                    return Poll::Ready(());
+                }
+                match running.poll_next_unpin(cx) {
+                    Poll::Ready(Some(())) => completed += 1,
+                    Poll::Ready(None) => {}
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+        });
+        futures::executor::block_on(with_context(run_all));
+        completed
+    }
+
+    fn take_intercepted(&self, waker: &Waker) -> Vec<BoxFuture> {
+        let mut intercepted = self
+            .intercepted
+            .lock()
+            .expect("Tasks::run_intercepted failed to acquire lock");
+        let Some(intercepted) = intercepted.as_mut() else {
+            return Vec::new();
+        };
+        intercepted.waker = Some(waker.clone());
+        intercepted
+            .queue
+            .drain(..)
+            .filter(|(_, abort, _)| !abort.is_aborted())
+            .map(|(_, _, task)| task)
+            .collect()
+    }
+
+    pub fn cancel_all(&self) {
+        let handles = std::mem::take(
+            &mut *self
+                .handles
+                .lock()
+                .expect("Tasks::cancel_all failed to acquire lock"),
+        );
+        handles.into_values().for_each(|handle| handle.abort());
+    }
+}
+
+impl Drop for Tasks {
+    fn drop(&mut self) {
+        self.cancel_all();
+    }
+}
+
+impl fmt::Debug for Tasks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let handles = self
+            .handles
+            .lock()
+            .expect("Tasks::fmt failed to acquire lock");
+        f.debug_set()
+            .entries(
+                handles
+                    .iter()
+                    .filter(|(_, handle)| !handle.is_finished())
+                    .map(|(key, _)| key),
+            )
+            .finish()
+    }
+}
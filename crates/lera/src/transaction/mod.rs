@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use crate::{Snapshot, StateWriter};
+
+/// A batch of mutations of a model state, created by `LeraModel::transaction`.
+///
+/// The transaction mutates a copy of the state, committed as one change unless
+/// it is rolled back. It holds the write lock of the state until then, so
+/// calling `mutate` or `transaction` on the model itself from within the
+/// transaction deadlocks, use [`Transaction::mutate`] instead. Reading the
+/// model returns the state before the transaction.
+pub struct Transaction<'a, S> {
+    writer: StateWriter<'a, S>,
+    initial: Arc<Snapshot<S>>,
+    current: S,
+}
+
+impl<'a, S: Clone> Transaction<'a, S> {
+    pub(crate) fn new(writer: StateWriter<'a, S>) -> Self {
+        let initial = writer.current();
+        let current = initial.state().clone();
+        Self {
+            writer,
+            initial,
+            current,
+        }
+    }
+
+    pub fn access<R>(&self, access: impl FnOnce(S) -> R) -> R {
+        access(self.current.clone())
+    }
+
+    /// Calls `read` with the state as mutated so far, without cloning it.
+    pub fn read<R>(&self, read: impl FnOnce(&S) -> R) -> R {
+        read(&self.current)
+    }
+
+    /// Mutates the state without notifying listeners, they are notified once
+    /// when the transaction commits.
+    pub fn mutate<R>(&mut self, mutate: impl FnOnce(&mut S) -> R) -> R {
+        mutate(&mut self.current)
+    }
+
+    /// The write lock, the state before the transaction and the mutated state.
+    pub(crate) fn into_parts(self) -> (StateWriter<'a, S>, Arc<Snapshot<S>>, S) {
+        (self.writer, self.initial, self.current)
+    }
+}
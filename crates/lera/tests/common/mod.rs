@@ -0,0 +1,120 @@
+//! Fixtures shared by the integration tests, each declaring `mod common;` and
+//! bringing `UniFfiTag` into its root.
+#![allow(dead_code, unused_imports, unused_macros)]
+
+use std::{
+    sync::{Condvar, Mutex},
+    time::Duration,
+};
+
+/// How long to wait for work running on another thread before failing.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The UniFFI tag of the test crate, referred to by the exported items.
+pub struct UniFfiTag;
+
+/// Records the changes delivered to it with their versions, made a listener of
+/// a state with [`recording_listener!`].
+pub struct RecordingListener<C> {
+    received: Mutex<Vec<(u64, C)>>,
+    changed: Condvar,
+}
+
+impl<C> Default for RecordingListener<C> {
+    fn default() -> Self {
+        Self {
+            received: Mutex::new(Vec::new()),
+            changed: Condvar::new(),
+        }
+    }
+}
+
+impl<C: Clone> RecordingListener<C> {
+    pub fn record(&self, change: C, version: u64) {
+        self.received.lock().unwrap().push((version, change));
+        self.changed.notify_all();
+    }
+
+    /// Waits until `count` changes were received, delivered by another thread,
+    /// and returns them.
+    pub fn wait_for(&self, count: usize) -> Vec<C> {
+        let (received, timeout) = self
+            .changed
+            .wait_timeout_while(self.received.lock().unwrap(), TIMEOUT, |received| {
+                received.len() < count
+            })
+            .unwrap();
+        assert!(
+            !timeout.timed_out(),
+            "timed out waiting for {} changes",
+            count
+        );
+        received.iter().map(|(_, change)| change.clone()).collect()
+    }
+
+    /// The changes received so far with their versions, oldest first.
+    pub fn received(&self) -> Vec<(u64, C)> {
+        self.received.lock().unwrap().clone()
+    }
+
+    /// Takes the changes received so far with their versions, oldest first.
+    pub fn take(&self) -> Vec<(u64, C)> {
+        std::mem::take(&mut *self.received.lock().unwrap())
+    }
+
+    /// The changes received so far, oldest first.
+    pub fn changes(&self) -> Vec<C> {
+        self.received()
+            .into_iter()
+            .map(|(_, change)| change)
+            .collect()
+    }
+
+    pub fn versions(&self) -> Vec<u64> {
+        self.received()
+            .into_iter()
+            .map(|(version, _)| version)
+            .collect()
+    }
+}
+
+/// Raised once by one thread, for another to wait on it.
+#[derive(Default)]
+pub struct Signal {
+    raised: Mutex<bool>,
+    condvar: Condvar,
+}
+
+impl Signal {
+    pub fn raise(&self) {
+        *self.raised.lock().unwrap() = true;
+        self.condvar.notify_all();
+    }
+
+    pub fn is_raised(&self) -> bool {
+        *self.raised.lock().unwrap()
+    }
+
+    /// Waits until the signal is raised, failing the test if it never is.
+    pub fn wait(&self) {
+        let (_raised, timeout) = self
+            .condvar
+            .wait_timeout_while(self.raised.lock().unwrap(), TIMEOUT, |raised| !*raised)
+            .unwrap();
+        assert!(!timeout.timed_out(), "timed out waiting for the signal");
+    }
+}
+
+/// Implements the generated listener trait `$listener`, receiving `$change`,
+/// for [`RecordingListener`].
+macro_rules! recording_listener {
+    ($listener:ident, $change:ty) => {
+        impl $listener for $crate::common::RecordingListener<$change> {
+            fn on_state_change(&self, change: $change, version: u64) {
+                self.record(change, version);
+            }
+        }
+    };
+}
+
+pub(crate) use recording_listener;
@@ -0,0 +1,94 @@
+mod common;
+
+use common::UniFfiTag;
+use lera::{Effect, LeraModel, Reducer};
+use std::sync::Arc;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct CounterState {
+    pub count: i32,
+    pub reached_ten: bool,
+}
+
+#[lera::actions]
+#[derive(Debug, PartialEq)]
+pub enum CounterAction {
+    Increment,
+    SetCount { count: i32 },
+    ReachedTen,
+}
+
+#[lera::model(state = CounterState, actions = CounterAction)]
+pub struct Counter {}
+
+#[lera::api]
+impl Counter {}
+
+impl Reducer for Counter {
+    type Action = CounterAction;
+
+    fn reduce(state: &mut CounterState, action: CounterAction) -> Effect<CounterAction> {
+        match action {
+            CounterAction::Increment => state.count += 1,
+            CounterAction::SetCount { count } => state.count = count,
+            CounterAction::ReachedTen => {
+                state.reached_ten = true;
+                return Effect::none();
+            }
+        }
+        if state.count == 10 {
+            Effect::send(CounterAction::ReachedTen)
+        } else {
+            Effect::none()
+        }
+    }
+}
+
+type RecordingListener = common::RecordingListener<CounterState>;
+common::recording_listener!(CounterStateChangeListener, CounterState);
+
+#[test]
+fn reduce_is_testable_without_a_model() {
+    let mut state = CounterState::default();
+
+    let effect = Counter::reduce(&mut state, CounterAction::Increment);
+    assert!(effect.is_none());
+    assert_eq!(state.count, 1);
+
+    let effect = Counter::reduce(&mut state, CounterAction::SetCount { count: 10 });
+    assert_eq!(effect.into_actions(), vec![CounterAction::ReachedTen]);
+    assert!(!state.reached_ten);
+}
+
+#[test]
+fn sent_actions_are_reduced_one_mutation_each() {
+    let listener = Arc::new(RecordingListener::default());
+    let counter = Counter::new(CounterState::default(), listener.clone());
+
+    counter.send(CounterAction::Increment);
+    counter.send(CounterAction::SetCount { count: 10 });
+
+    let counts: Vec<_> = listener
+        .changes()
+        .into_iter()
+        .map(|state| (state.count, state.reached_ten))
+        .collect();
+    assert_eq!(counts, vec![(1, false), (10, false), (10, true)]);
+    assert_eq!(counter.state_version(), 3);
+}
+
+#[test]
+fn exported_send_action_reduces() {
+    let counter = Counter::new(
+        CounterState::default(),
+        Arc::new(RecordingListener::default()),
+    );
+
+    counter
+        .clone()
+        .send_action(CounterAction::SetCount { count: 9 });
+    counter.clone().send_action(CounterAction::Increment);
+
+    assert!(counter.read(|state| state.reached_ten));
+}
@@ -0,0 +1,218 @@
+mod common;
+
+use common::UniFfiTag;
+use lera::LeraModel;
+use std::sync::Arc;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct CounterState {
+    pub count: i64,
+}
+
+#[lera::model(state = CounterState)]
+pub struct Counter {}
+
+#[lera::api]
+impl Counter {
+    pub fn increment(&self) {
+        self.mutate(|state| state.count += 1);
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct ScreenState {
+    pub title: String,
+    pub counter: CounterState,
+}
+
+#[lera::model(state = ScreenState)]
+pub struct Screen {
+    #[lera::child]
+    counter: Arc<Counter>,
+}
+
+#[lera::api]
+impl Screen {
+    pub fn rename(&self, title: String) {
+        self.mutate(|state| state.title = title);
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state(diff)]
+pub struct ScoreState {
+    pub points: u32,
+    pub misses: u32,
+}
+
+#[lera::model(state = ScoreState)]
+pub struct Score {}
+
+#[lera::api]
+impl Score {
+    pub fn hit(&self) {
+        self.mutate(|state| state.points += 1);
+    }
+
+    pub fn miss(&self) {
+        self.mutate(|state| state.misses += 1);
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct GameState {
+    pub score: ScoreState,
+}
+
+#[lera::model(state = GameState)]
+pub struct Game {
+    #[lera::child]
+    score: Arc<Score>,
+}
+
+#[lera::api]
+impl Game {}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct FormState {
+    pub name: String,
+    pub counter: CounterState,
+}
+
+#[lera::model(state = FormState, history = 10)]
+pub struct Form {
+    #[lera::child]
+    counter: Arc<Counter>,
+}
+
+#[lera::api]
+impl Form {
+    pub fn rename(&self, name: String) {
+        self.mutate(|state| state.name = name);
+    }
+
+    pub fn reset_counter(&self) {
+        self.mutate(|state| state.counter = CounterState::default());
+    }
+}
+
+type RecordingListener = common::RecordingListener<ScreenState>;
+common::recording_listener!(ScreenStateChangeListener, ScreenState);
+common::recording_listener!(CounterStateChangeListener, CounterState);
+common::recording_listener!(GameStateChangeListener, GameState);
+common::recording_listener!(FormStateChangeListener, FormState);
+
+fn screen_state(title: &str, count: i64) -> ScreenState {
+    ScreenState {
+        title: title.to_owned(),
+        counter: CounterState { count },
+    }
+}
+
+#[test]
+fn child_is_created_with_the_projected_state() {
+    let screen = Screen::new(screen_state("", 3), Arc::new(ScreenNoopListener));
+
+    assert_eq!(screen.counter().access(|state| state.count), 3);
+}
+
+#[test]
+fn child_changes_are_projected_into_the_parent_state() {
+    let listener = Arc::new(RecordingListener::default());
+    let screen = Screen::new(ScreenState::default(), listener.clone());
+
+    screen.counter().increment();
+    screen.rename("Counter".to_owned());
+    screen.counter().increment();
+
+    assert_eq!(
+        listener.changes(),
+        vec![
+            screen_state("", 1),
+            screen_state("Counter", 1),
+            screen_state("Counter", 2)
+        ]
+    );
+}
+
+#[test]
+fn child_diffs_are_projected_into_the_parent_state() {
+    let listener = Arc::new(common::RecordingListener::<GameState>::default());
+    let game = Game::new(GameState::default(), listener.clone());
+
+    game.score().hit();
+    game.score().miss();
+    game.score().hit();
+
+    let score = |points, misses| GameState {
+        score: ScoreState { points, misses },
+    };
+    assert_eq!(
+        listener.changes(),
+        vec![score(1, 0), score(1, 1), score(2, 1)]
+    );
+}
+
+#[test]
+fn child_shares_the_rust_instance_and_notifies_its_own_subscribers() {
+    let screen = Screen::new(ScreenState::default(), Arc::new(ScreenNoopListener));
+    let listener = Arc::new(common::RecordingListener::<CounterState>::default());
+    let _subscription = screen.counter().subscribe(listener.clone());
+
+    assert!(Arc::ptr_eq(&screen.counter(), &screen.counter()));
+    screen.counter().increment();
+
+    assert_eq!(listener.changes(), vec![CounterState { count: 1 }]);
+    assert_eq!(screen.access(|state| state.counter.count), 1);
+}
+
+#[test]
+fn dropping_the_parent_drops_the_child() {
+    let screen = Screen::new(ScreenState::default(), Arc::new(ScreenNoopListener));
+    let counter = Arc::downgrade(&screen.counter());
+
+    drop(screen);
+
+    assert!(counter.upgrade().is_none());
+}
+
+#[test]
+fn parent_undo_across_a_child_change_keeps_the_projection() {
+    let form = Form::new(FormState::default(), Arc::new(FormNoopListener));
+
+    form.rename("a".to_owned());
+    form.counter().increment();
+    form.rename("b".to_owned());
+    form.counter().increment();
+
+    form.undo();
+    assert_eq!(form.access(|state| state.name), "a");
+    assert_eq!(form.access(|state| state.counter.count), 2);
+    // The child's changes are not undoable in the parent.
+    form.undo();
+    assert_eq!(form.access(|state| state.name), "");
+    assert!(!form.can_undo());
+    form.redo();
+    assert_eq!(form.access(|state| state.name), "a");
+
+    assert_eq!(form.counter().access(|state| state.count), 2);
+    assert_eq!(form.access(|state| state.counter.count), 2);
+}
+
+#[test]
+fn parent_writes_to_the_projection_are_discarded() {
+    let listener = Arc::new(common::RecordingListener::<FormState>::default());
+    let form = Form::new(FormState::default(), listener.clone());
+    form.counter().increment();
+
+    form.reset_counter();
+
+    assert_eq!(form.counter().access(|state| state.count), 1);
+    assert_eq!(form.access(|state| state.counter.count), 1);
+    assert_eq!(listener.changes().len(), 1);
+    assert!(!form.can_undo());
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use common::UniFfiTag;
+use lera::LeraModel;
+use std::sync::Arc;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, uniffi::Record)]
+pub struct CartItem {
+    pub price: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct CartState {
+    pub items: Vec<CartItem>,
+}
+
+#[lera::computed]
+impl CartState {
+    fn total(&self) -> u64 {
+        self.items.iter().map(|item| item.price).sum()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+#[lera::model(state = CartState)]
+pub struct Cart {}
+
+#[lera::api]
+impl Cart {
+    pub fn add(&self, price: u64) {
+        self.mutate(|state| state.items.push(CartItem { price }));
+    }
+}
+
+type RecordingListener = common::RecordingListener<CartStateChange>;
+common::recording_listener!(CartStateChangeListener, CartStateChange);
+
+/// Private, like its computed properties.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state(diff)]
+struct TallyState {
+    count: u32,
+    label: Option<String>,
+}
+
+#[lera::computed]
+impl TallyState {
+    fn is_even(&self) -> bool {
+        self.count.is_multiple_of(2)
+    }
+}
+
+#[test]
+fn computed_properties_are_evaluated_for_the_state() {
+    assert_eq!(
+        cart_state_computed(CartState::default()),
+        CartStateComputed {
+            total: 0,
+            is_empty: true
+        }
+    );
+    assert_eq!(
+        cart_state_computed(CartState {
+            items: vec![CartItem { price: 3 }, CartItem { price: 4 }]
+        }),
+        CartStateComputed {
+            total: 7,
+            is_empty: false
+        }
+    );
+}
+
+#[test]
+fn changes_are_delivered_with_the_computed_properties_of_the_new_state() {
+    let listener = Arc::new(RecordingListener::default());
+    let cart = Cart::new(CartState::default(), listener.clone());
+
+    cart.add(3);
+    cart.add(4);
+
+    assert_eq!(
+        listener
+            .changes()
+            .into_iter()
+            .map(|change| (change.state.items.len(), change.computed))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                1,
+                CartStateComputed {
+                    total: 3,
+                    is_empty: false
+                }
+            ),
+            (
+                2,
+                CartStateComputed {
+                    total: 7,
+                    is_empty: false
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn merged_changes_hold_the_computed_properties_of_the_newer_state() {
+    use lera::StateChange;
+
+    let zero = TallyState::default();
+    let one = TallyState {
+        count: 1,
+        label: None,
+    };
+    let two = TallyState {
+        count: 2,
+        label: Some("two".to_owned()),
+    };
+
+    let merged = TallyState::merge(
+        TallyState::change(&zero, &one),
+        TallyState::change(&one, &two),
+    );
+
+    assert_eq!(merged.diff.count, Some(2));
+    assert_eq!(merged.diff.label.as_deref(), Some("two"));
+    assert_eq!(merged.computed, TallyStateComputed { is_even: true });
+}
@@ -0,0 +1,199 @@
+mod common;
+
+use common::UniFfiTag;
+use lera::{Effect, LeraModel, Mutation, Reducer, tasks::TaskKey};
+use std::{sync::Arc, time::Duration};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct SearchState {
+    pub query: String,
+    pub results: Vec<String>,
+    pub log: Vec<String>,
+}
+
+#[lera::model(state = SearchState)]
+pub struct Search {}
+
+async fn fetch_results(query: String) -> Vec<String> {
+    vec![format!("{} 1", query), format!("{} 2", query)]
+}
+
+#[lera::api]
+impl Search {
+    pub fn search(self: &Arc<Self>, query: String) -> Effect<Mutation<SearchState>> {
+        self.mutate(|state| state.query = query.clone());
+        Effect::run_then_mutate(fetch_results(query), |state: &mut SearchState, results| {
+            state.results = results;
+        })
+        .cancellable("search")
+    }
+
+    pub fn clear(self: &Arc<Self>) -> Effect<Mutation<SearchState>> {
+        Effect::merge([
+            Effect::cancel("search"),
+            Effect::mutate(|state: &mut SearchState| state.results.clear()),
+        ])
+    }
+
+    pub fn log_in_order(self: &Arc<Self>) -> Effect<Mutation<SearchState>> {
+        let log = |line: &'static str| move |state: &mut SearchState| state.log.push(line.into());
+        Effect::concat([
+            Effect::mutate(log("first")),
+            Effect::run_then_mutate(
+                lera::executor::sleep(Duration::from_millis(5)),
+                move |state, ()| log("second")(state),
+            ),
+            Effect::mutate(log("third")),
+        ])
+    }
+}
+
+common::recording_listener!(SearchStateChangeListener, SearchState);
+
+fn search() -> Arc<Search> {
+    let search = Arc::new(Search::without_listener(SearchState::default()));
+    search.get_tasks().intercept();
+    search
+}
+
+fn results(search: &Search) -> Vec<String> {
+    search.read(|state| state.results.clone())
+}
+
+#[test]
+fn effects_run_then_mutate_with_their_result() {
+    let search = search();
+
+    search.search("cats".to_owned());
+
+    assert_eq!(
+        search.get_tasks().intercepted(),
+        vec![TaskKey::from("search")]
+    );
+    assert!(results(&search).is_empty());
+    assert_eq!(search.get_tasks().run_intercepted(), 1);
+    assert_eq!(results(&search), vec!["cats 1", "cats 2"]);
+}
+
+#[test]
+fn cancellable_effects_cancel_the_running_one() {
+    let search = search();
+
+    search.search("ca".to_owned());
+    search.search("cats".to_owned());
+    assert_eq!(search.get_tasks().intercepted().len(), 1);
+    search.get_tasks().run_intercepted();
+    assert_eq!(results(&search), vec!["cats 1", "cats 2"]);
+
+    search.search("dogs".to_owned());
+    search.clear();
+    assert!(search.get_tasks().intercepted().is_empty());
+    assert_eq!(search.get_tasks().run_intercepted(), 0);
+    assert!(results(&search).is_empty());
+}
+
+#[cfg(any(feature = "tokio", feature = "smol"))]
+#[test]
+fn concatenated_effects_run_one_after_the_other() {
+    let search = search();
+
+    search.log_in_order();
+    // Only the first one runs right away, the others wait for the future.
+    assert_eq!(search.read(|state| state.log.clone()), vec!["first"]);
+    search.get_tasks().run_intercepted();
+
+    assert_eq!(
+        search.read(|state| state.log.clone()),
+        vec!["first", "second", "third"]
+    );
+}
+
+#[cfg(any(feature = "tokio", feature = "smol"))]
+#[test]
+fn effects_run_on_the_executor_unless_intercepted() {
+    let listener = Arc::new(common::RecordingListener::<SearchState>::default());
+    let search = Search::new(SearchState::default(), listener.clone());
+
+    search.search("cats".to_owned());
+
+    // The query, then the results once the effect ran on another thread.
+    let changes = listener.wait_for(2);
+    assert_eq!(changes[1].results, vec!["cats 1", "cats 2"]);
+    assert_eq!(results(&search), vec!["cats 1", "cats 2"]);
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct ProfileState {
+    pub name: Option<String>,
+    pub is_loading: bool,
+}
+
+#[lera::actions]
+#[derive(Debug, PartialEq)]
+pub enum ProfileAction {
+    Load,
+    Loaded { name: String },
+}
+
+#[lera::model(state = ProfileState, actions = ProfileAction)]
+pub struct Profile {}
+
+#[lera::api]
+impl Profile {}
+
+impl Reducer for Profile {
+    type Action = ProfileAction;
+
+    fn reduce(state: &mut ProfileState, action: ProfileAction) -> Effect<ProfileAction> {
+        match action {
+            ProfileAction::Load => {
+                state.is_loading = true;
+                Effect::run(async {
+                    ProfileAction::Loaded {
+                        name: "Ferris".to_owned(),
+                    }
+                })
+            }
+            ProfileAction::Loaded { name } => {
+                state.is_loading = false;
+                state.name = Some(name);
+                Effect::none()
+            }
+        }
+    }
+}
+
+#[test]
+fn reducer_effects_send_their_result_back() {
+    let profile = Arc::new(Profile::without_listener(ProfileState::default()));
+    profile.get_tasks().intercept();
+
+    profile.send(ProfileAction::Load);
+    assert!(profile.read(|state| state.is_loading));
+    assert_eq!(profile.get_tasks().intercepted().len(), 1);
+    profile.get_tasks().run_intercepted();
+
+    assert_eq!(
+        profile.access(|state| state),
+        ProfileState {
+            name: Some("Ferris".to_owned()),
+            is_loading: false,
+        }
+    );
+}
+
+#[test]
+fn effects_describe_themselves() {
+    let effect = Effect::merge([
+        Effect::send(ProfileAction::Load),
+        Effect::run(async { ProfileAction::Load }).cancellable("load"),
+    ]);
+
+    assert_eq!(
+        format!("{:?}", effect),
+        r#"Effect::merge([Effect::send(Load), Effect::run(..).cancellable("load")])"#
+    );
+    assert_eq!(effect.into_actions(), vec![ProfileAction::Load]);
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use common::UniFfiTag;
+use lera::{EventEmitter, LeraModel};
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct CounterState {
+    pub count: i32,
+}
+
+#[lera::events]
+#[derive(Clone, Debug, PartialEq)]
+pub enum CounterEvent {
+    ReachedTen,
+    ShowToast { message: String },
+}
+
+#[lera::model(state = CounterState, events = CounterEvent)]
+pub struct Counter {}
+
+#[lera::api]
+impl Counter {
+    pub fn increment(&self) {
+        let count = self.mutate(|state| {
+            state.count += 1;
+            state.count
+        });
+        if count == 10 {
+            self.emit(CounterEvent::ReachedTen);
+        }
+    }
+}
+
+#[derive(Default)]
+struct RecordingListener {
+    events: Mutex<Vec<CounterEvent>>,
+}
+
+impl CounterEventListener for RecordingListener {
+    fn on_event(&self, event: CounterEvent) {
+        self.events.lock().unwrap().push(event);
+    }
+}
+
+fn counter(count: i32) -> Arc<Counter> {
+    Arc::new(Counter::without_listener(CounterState { count }))
+}
+
+#[test]
+fn events_are_delivered_to_subscribed_listeners() {
+    let counter = counter(8);
+    let listener = Arc::new(RecordingListener::default());
+    let _subscription = counter.subscribe_events(listener.clone());
+
+    counter.increment();
+    assert!(listener.events.lock().unwrap().is_empty());
+    counter.increment();
+
+    assert_eq!(
+        *listener.events.lock().unwrap(),
+        vec![CounterEvent::ReachedTen]
+    );
+    assert!(counter.get_events().buffered().is_empty());
+}
+
+#[test]
+fn events_are_buffered_while_nobody_listens() {
+    let counter = counter(9);
+
+    counter.increment();
+    counter.emit(CounterEvent::ShowToast {
+        message: "Ten!".to_owned(),
+    });
+    assert_eq!(counter.get_events().buffered().len(), 2);
+
+    let listener = Arc::new(RecordingListener::default());
+    let _subscription = counter.subscribe_events(listener.clone());
+
+    assert_eq!(
+        *listener.events.lock().unwrap(),
+        vec![
+            CounterEvent::ReachedTen,
+            CounterEvent::ShowToast {
+                message: "Ten!".to_owned()
+            }
+        ]
+    );
+    assert!(counter.get_events().buffered().is_empty());
+}
+
+#[test]
+fn events_are_buffered_again_once_cancelled() {
+    let counter = counter(0);
+    let listener = Arc::new(RecordingListener::default());
+    let subscription = counter.subscribe_events(listener.clone());
+
+    subscription.cancel();
+    counter.emit(CounterEvent::ReachedTen);
+
+    assert!(listener.events.lock().unwrap().is_empty());
+    assert_eq!(
+        counter.get_events().buffered(),
+        vec![CounterEvent::ReachedTen]
+    );
+}
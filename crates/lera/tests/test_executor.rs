@@ -0,0 +1,53 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use common::UniFfiTag;
+use lera::LeraModel;
+use std::{sync::Arc, time::Duration};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct LoaderState {
+    pub loaded: u32,
+}
+
+#[lera::model(state = LoaderState)]
+pub struct Loader {}
+
+#[lera::api]
+impl Loader {
+    pub async fn load(&self, delay_ms: u64) -> u32 {
+        // Needs a tokio context, which UniFFI polling does not provide.
+        tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+        self.mutate(|state| {
+            state.loaded += 1;
+            state.loaded
+        })
+    }
+}
+
+#[test]
+fn async_api_methods_run_in_executor_context() {
+    let loader = Loader::without_listener(LoaderState::default());
+
+    // Polled outside of any tokio runtime, like UniFFI does.
+    let loaded = futures::executor::block_on(loader.load(1));
+
+    assert_eq!(loaded, 1);
+}
+
+#[test]
+fn spawned_futures_run_on_executor() {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    lera::executor::spawn(async move {
+        lera::executor::sleep(Duration::from_millis(1)).await;
+        sender
+            .send(std::thread::current().name().map(str::to_owned))
+            .unwrap();
+    });
+
+    let thread_name = receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+
+    assert_eq!(thread_name.as_deref(), Some("lera-rt"));
+}
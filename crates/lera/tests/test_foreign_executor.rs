@@ -0,0 +1,82 @@
+#![cfg(feature = "foreign")]
+
+mod common;
+
+use common::{Signal, UniFfiTag};
+use lera::LeraModel;
+use std::{
+    sync::{
+        Arc, Mutex,
+        mpsc::{Sender, channel},
+    },
+    time::Duration,
+};
+
+lera::lera_setup_ffi_for_executor!();
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct MetronomeState {
+    pub beats: u32,
+}
+
+#[lera::model(state = MetronomeState)]
+pub struct Metronome {}
+
+common::recording_listener!(MetronomeStateChangeListener, MetronomeState);
+
+#[lera::api]
+impl Metronome {
+    pub fn start(self: &Arc<Self>) {
+        self.spawn_interval("beat", Duration::from_millis(2), |metronome| {
+            metronome.mutate(|state| {
+                state.beats += 1;
+                state.beats < 3
+            })
+        });
+    }
+}
+
+/// Runs every task on a thread of its own, like Swift `Task`s would, and hands
+/// the timers to the test, which fires them.
+struct ThreadExecutor {
+    timers: Mutex<Sender<(u64, Arc<ForeignTimer>)>>,
+    finished: Arc<Signal>,
+}
+
+impl ForeignExecutor for ThreadExecutor {
+    fn spawn(&self, task: Arc<ForeignTask>) {
+        let finished = self.finished.clone();
+        std::thread::spawn(move || {
+            futures::executor::block_on(task.run());
+            finished.raise();
+        });
+    }
+
+    fn schedule(&self, delay_ms: u64, timer: Arc<ForeignTimer>) {
+        self.timers.lock().unwrap().send((delay_ms, timer)).unwrap();
+    }
+}
+
+#[test]
+fn tasks_run_on_installed_foreign_executor() {
+    let (timers, scheduled) = channel();
+    let finished = Arc::new(Signal::default());
+    install_foreign_executor(Arc::new(ThreadExecutor {
+        timers: Mutex::new(timers),
+        finished: finished.clone(),
+    }));
+    let listener = Arc::new(common::RecordingListener::<MetronomeState>::default());
+    let metronome = Metronome::new(MetronomeState::default(), listener.clone());
+
+    metronome.start();
+    for beats in 1..=3 {
+        let (_delay_ms, timer) = scheduled.recv_timeout(Duration::from_secs(5)).unwrap();
+        timer.fire();
+        assert_eq!(listener.wait_for(beats).len(), beats);
+    }
+    finished.wait();
+
+    assert_eq!(metronome.access(|state| state.beats), 3);
+    assert!(!metronome.is_task_running("beat"));
+}
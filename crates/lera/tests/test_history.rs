@@ -0,0 +1,96 @@
+mod common;
+
+use common::UniFfiTag;
+use lera::LeraModel;
+use std::{convert::Infallible, sync::Arc};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct SketchState {
+    pub strokes: u32,
+}
+
+#[lera::model(state = SketchState, history = 3)]
+pub struct Sketch {}
+
+#[lera::api]
+impl Sketch {
+    pub fn draw(&self) {
+        self.mutate(|state| state.strokes += 1);
+    }
+
+    pub fn draw_twice(&self) {
+        let Ok(()) = self.transaction(|tx| {
+            tx.mutate(|state| state.strokes += 1);
+            tx.mutate(|state| state.strokes += 1);
+            Ok::<_, Infallible>(())
+        });
+    }
+}
+
+type RecordingListener = common::RecordingListener<SketchState>;
+common::recording_listener!(SketchStateChangeListener, SketchState);
+
+fn strokes(model: &Sketch) -> u32 {
+    model.access(|state| state.strokes)
+}
+
+#[test]
+fn undo_and_redo_restore_states_and_notify() {
+    let listener = Arc::new(RecordingListener::default());
+    let model = Sketch::new(SketchState::default(), listener.clone());
+    assert!(!model.can_undo());
+
+    model.draw();
+    model.draw();
+    model.undo();
+    assert_eq!(strokes(&model), 1);
+    assert!(model.can_undo());
+    assert!(model.can_redo());
+
+    model.redo();
+    assert_eq!(strokes(&model), 2);
+    assert!(!model.can_redo());
+
+    let versions: Vec<(u64, u32)> = listener
+        .received()
+        .into_iter()
+        .map(|(version, state)| (version, state.strokes))
+        .collect();
+    assert_eq!(versions, vec![(1, 1), (2, 2), (3, 1), (4, 2)]);
+}
+
+#[test]
+fn history_is_bounded() {
+    let model = Sketch::without_listener(SketchState::default());
+
+    for _ in 0..5 {
+        model.draw();
+    }
+    while LeraModel::undo(&model) {}
+
+    assert_eq!(strokes(&model), 2);
+}
+
+#[test]
+fn change_after_undo_clears_redo() {
+    let model = Sketch::without_listener(SketchState::default());
+
+    model.draw();
+    model.undo();
+    model.draw();
+
+    assert!(!model.can_redo());
+    assert!(!LeraModel::redo(&model));
+}
+
+#[test]
+fn transaction_is_undone_as_one_step() {
+    let model = Sketch::without_listener(SketchState::default());
+
+    model.draw_twice();
+    model.undo();
+
+    assert_eq!(strokes(&model), 0);
+    assert!(!model.can_undo());
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use common::UniFfiTag;
+use lera::LeraModel;
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+static INIT_CALLS: AtomicUsize = AtomicUsize::new(0);
+static DROP_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct HookedState {
+    pub count: i64,
+    pub is_started: bool,
+}
+
+#[lera::model(state = HookedState)]
+pub struct Hooked {}
+
+impl Hooked {
+    #[lera::on_init]
+    fn set_up(self: &Arc<Self>) {
+        INIT_CALLS.fetch_add(1, Ordering::SeqCst);
+        self.mutate(|state| state.is_started = true);
+    }
+}
+
+#[lera::api]
+impl Hooked {
+    #[lera::on_drop]
+    fn tear_down(&self) {
+        DROP_CALLS.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn increment(self: &Arc<Self>) {
+        self.mutate(|state| state.count += 1);
+    }
+}
+
+#[test]
+fn hooks_run_on_init_and_on_drop() {
+    let listener: Arc<dyn HookedStateChangeListener> = Arc::new(HookedNoopListener);
+    let model = Hooked::new(HookedState::default(), listener);
+    assert_eq!(INIT_CALLS.load(Ordering::SeqCst), 1);
+    assert!(model.access(|state| state.is_started));
+
+    model.increment();
+    assert_eq!(model.access(|state| state.count), 1);
+    assert_eq!(DROP_CALLS.load(Ordering::SeqCst), 0);
+
+    drop(model);
+    assert_eq!(INIT_CALLS.load(Ordering::SeqCst), 1);
+    assert_eq!(DROP_CALLS.load(Ordering::SeqCst), 1);
+}
+
+static PLAIN_DROP_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct PlainState {
+    pub count: i64,
+}
+
+#[lera::model(state = PlainState)]
+pub struct Plain {}
+
+#[lera::api]
+impl Plain {}
+
+impl Plain {
+    #[lera::on_drop]
+    fn tear_down(&self) {
+        PLAIN_DROP_CALLS.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn on_drop_runs_when_declared_in_a_plain_impl_block() {
+    let listener: Arc<dyn PlainStateChangeListener> = Arc::new(PlainNoopListener);
+    let model = Plain::new(PlainState::default(), listener);
+    assert_eq!(PLAIN_DROP_CALLS.load(Ordering::SeqCst), 0);
+
+    drop(model);
+    assert_eq!(PLAIN_DROP_CALLS.load(Ordering::SeqCst), 1);
+}
@@ -1,6 +1,7 @@
-use std::sync::Arc;
+mod common;
 
-pub struct UniFfiTag;
+use common::UniFfiTag;
+use std::sync::Arc;
 
 lera::lera_setup_ffi_for_logging!();
 use log::debug;
@@ -0,0 +1,101 @@
+mod common;
+
+use common::UniFfiTag;
+use lera::{
+    LeraModel,
+    persistence::{Storage, assert_fixtures_round_trip, install_storage},
+};
+use serde_json::{Value, json};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[lera::state(persist = "test_migrations_profile", version = 3)]
+pub struct ProfileState {
+    pub first_name: String,
+    pub last_name: String,
+    pub nickname: Option<String>,
+}
+
+/// Version 1 had a single `name`.
+fn migrate_v1_to_v2(mut state: Value) -> Result<Value, String> {
+    let name = state["name"].take();
+    let name = name.as_str().ok_or("missing name")?;
+    let (first_name, last_name) = name.split_once(' ').unwrap_or((name, ""));
+    Ok(json!({ "first_name": first_name, "last_name": last_name }))
+}
+
+fn migrate_v2_to_v3(mut state: Value) -> Value {
+    state["nickname"] = Value::Null;
+    state
+}
+
+#[lera::model(state = ProfileState)]
+pub struct Profile {}
+
+#[lera::api]
+impl Profile {}
+
+#[derive(Default)]
+struct InMemoryStorage {
+    values: Mutex<HashMap<String, String>>,
+}
+
+impl Storage for InMemoryStorage {
+    fn load(&self, key: &str) -> Option<String> {
+        self.values.lock().unwrap().get(key).cloned()
+    }
+
+    fn save(&self, key: &str, value: String) {
+        self.values.lock().unwrap().insert(key.to_owned(), value);
+    }
+}
+
+fn ada() -> ProfileState {
+    ProfileState {
+        first_name: "Ada".to_owned(),
+        last_name: "Lovelace".to_owned(),
+        nickname: None,
+    }
+}
+
+#[test]
+fn fixtures_of_every_version_round_trip() {
+    let states = assert_fixtures_round_trip::<ProfileState>(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/profile_state"
+    ));
+
+    assert_eq!(states, vec![ada(), ada(), ada()]);
+}
+
+/// The storage is global, so a single test uses it from start to end.
+#[test]
+fn saved_states_are_migrated_when_restored() {
+    let storage = Arc::new(InMemoryStorage::default());
+    install_storage(storage.clone());
+    let restore = |saved: &str| {
+        storage.save("test_migrations_profile", saved.to_owned());
+        Profile::new(ProfileState::default(), Arc::new(ProfileNoopListener)).access(|state| state)
+    };
+
+    // Saved before the state was versioned.
+    assert_eq!(restore(r#"{"name":"Ada Lovelace"}"#), ada());
+    assert_eq!(
+        restore(r#"{"lera.version":2,"state":{"first_name":"Ada","last_name":"Lovelace"}}"#),
+        ada()
+    );
+
+    // A failing migration or data from a newer version restores the initial state.
+    assert_eq!(
+        restore(r#"{"lera.version":1,"state":{}}"#),
+        ProfileState::default()
+    );
+    assert_eq!(
+        restore(r#"{"lera.version":4,"state":{"first_name":"Ada"}}"#),
+        ProfileState::default()
+    );
+    assert_eq!(restore(r#"{"lera.version":2}"#), ProfileState::default());
+}
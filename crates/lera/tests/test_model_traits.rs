@@ -0,0 +1,72 @@
+mod common;
+
+use common::UniFfiTag;
+use lera::LeraModel;
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct GaugeState {
+    pub level: u8,
+}
+
+/// Not `PartialEq`, `Hash` nor `Debug`.
+#[derive(Default)]
+pub struct Sensor(Mutex<u8>);
+
+#[lera::model(state = GaugeState)]
+pub struct Gauge {
+    label: String,
+    #[lera(skip_eq, skip_hash, skip_debug)]
+    sensor: Sensor,
+}
+
+#[lera::api]
+impl Gauge {
+    pub fn raise(self: &Arc<Self>) {
+        self.mutate(|state| state.level += 1);
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[lera::state]
+pub struct ThermometerState {
+    pub celsius: f64,
+}
+
+#[lera::model(state = ThermometerState, traits(debug, display))]
+pub struct Thermometer {}
+
+#[lera::api]
+impl Thermometer {
+    pub fn heat(self: &Arc<Self>) {
+        self.mutate(|state| state.celsius += 0.5);
+    }
+}
+
+#[test]
+fn skipped_fields_do_not_take_part_in_eq_and_debug() {
+    let a = Gauge::without_listener(GaugeState { level: 1 }, "a".to_owned(), Sensor::default());
+    let b = Gauge::without_listener(GaugeState { level: 1 }, "a".to_owned(), Sensor::default());
+    *b.sensor.0.lock().unwrap() = 42;
+    assert_eq!(a, b);
+
+    let c = Gauge::without_listener(GaugeState { level: 1 }, "c".to_owned(), Sensor::default());
+    assert_ne!(a, c);
+
+    assert_eq!(
+        format!("{:?}", a),
+        r#"Gauge { state: GaugeState { level: 1 }, label: "a" }"#
+    );
+}
+
+#[test]
+fn model_without_user_fields_formats_like_its_state() {
+    let thermometer = Thermometer::without_listener(ThermometerState { celsius: 1.5 });
+    thermometer.mutate(|state| state.celsius += 0.5);
+    assert_eq!(
+        format!("{:?}", thermometer),
+        "ThermometerState { celsius: 2.0 }"
+    );
+    assert_eq!(format!("{}", thermometer), format!("{:?}", thermometer));
+}
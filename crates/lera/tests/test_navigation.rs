@@ -0,0 +1,93 @@
+mod common;
+
+use common::UniFfiTag;
+use lera::LeraModel;
+use std::sync::Arc;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[lera::navigation]
+pub enum Destination {
+    Settings,
+    Profile { user_id: String },
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct AppState {
+    pub navigation: DestinationStack,
+}
+
+#[lera::model(state = AppState, navigation = navigation)]
+pub struct App {}
+
+#[lera::api]
+impl App {
+    pub fn profile_tapped(&self, user_id: String) {
+        self.mutate(|state| state.navigation.push(Destination::Profile { user_id }));
+    }
+
+    pub fn settings_tapped(&self) {
+        self.mutate(|state| state.navigation.push(Destination::Settings));
+    }
+
+    pub fn sign_out_tapped(&self) {
+        self.mutate(|state| state.navigation.pop_to_root());
+    }
+}
+
+fn path(model: &App) -> Vec<Destination> {
+    model.access(|state| state.navigation.path)
+}
+
+fn profile(user_id: &str) -> Destination {
+    Destination::Profile {
+        user_id: user_id.to_owned(),
+    }
+}
+
+#[test]
+fn push_pop_and_pop_to_root() {
+    let mut stack = DestinationStack::default();
+    assert!(stack.is_empty());
+    assert_eq!(stack.top(), None);
+
+    stack.push(Destination::Settings);
+    stack.push(profile("ada"));
+    assert_eq!(stack.len(), 2);
+    assert_eq!(stack.top(), Some(&profile("ada")));
+
+    assert_eq!(stack.pop(), Some(profile("ada")));
+    assert_eq!(stack.path, vec![Destination::Settings]);
+
+    stack.push(profile("ada"));
+    stack.pop_to_root();
+    assert!(stack.is_empty());
+    assert_eq!(stack.pop(), None);
+}
+
+#[test]
+fn model_navigates_through_its_state() {
+    let model = App::without_listener(AppState::default());
+
+    model.settings_tapped();
+    model.profile_tapped("ada".to_owned());
+    assert_eq!(path(&model), vec![Destination::Settings, profile("ada")]);
+
+    model.sign_out_tapped();
+    assert_eq!(path(&model), vec![]);
+}
+
+#[test]
+fn navigating_back_in_the_ui_truncates_the_stack() {
+    let model = App::without_listener(AppState::default());
+    model.settings_tapped();
+    model.profile_tapped("ada".to_owned());
+    model.profile_tapped("grace".to_owned());
+
+    model.navigation_did_pop(1);
+    assert_eq!(path(&model), vec![Destination::Settings]);
+
+    // Popping to a depth deeper than the stack does nothing.
+    model.navigation_did_pop(3);
+    assert_eq!(path(&model), vec![Destination::Settings]);
+}
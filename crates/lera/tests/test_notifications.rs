@@ -0,0 +1,100 @@
+mod common;
+
+use common::UniFfiTag;
+use lera::LeraModel;
+use std::sync::{Arc, OnceLock, Weak};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct StepsState {
+    pub count: u64,
+}
+
+#[lera::model(state = StepsState)]
+pub struct Steps {}
+
+#[lera::api]
+impl Steps {
+    pub fn step(self: &Arc<Self>) {
+        self.mutate(|state| state.count += 1);
+    }
+}
+
+type RecordingListener = common::RecordingListener<StepsState>;
+common::recording_listener!(StepsStateChangeListener, StepsState);
+
+fn counts(listener: &RecordingListener) -> Vec<(u64, u64)> {
+    listener
+        .received()
+        .into_iter()
+        .map(|(version, state)| (version, state.count))
+        .collect()
+}
+
+#[test]
+fn concurrent_mutations_are_delivered_in_version_order() {
+    let listener = Arc::new(RecordingListener::default());
+    let model = Steps::new(StepsState::default(), listener.clone());
+    assert_eq!(model.state_version(), 0);
+
+    let threads = (0..8)
+        .map(|_| {
+            let model = model.clone();
+            std::thread::spawn(move || (0..100).for_each(|_| model.step()))
+        })
+        .collect::<Vec<_>>();
+    threads.into_iter().for_each(|t| t.join().unwrap());
+
+    let expected = (1..=800).map(|n| (n, n)).collect::<Vec<_>>();
+    assert_eq!(counts(&listener), expected);
+    assert_eq!(model.state_version(), 800);
+}
+
+#[test]
+fn unchanged_state_does_not_bump_version() {
+    let listener = Arc::new(RecordingListener::default());
+    let model = Steps::new(StepsState::default(), listener.clone());
+
+    model.mutate(|_| {});
+    model.step();
+
+    assert_eq!(counts(&listener), vec![(1, 1)]);
+    assert_eq!(model.state_version(), 1);
+}
+
+/// Steps the model again from within `on_state_change` until `limit` is reached.
+struct ReentrantListener {
+    model: OnceLock<Weak<Steps>>,
+    limit: u64,
+    recording: RecordingListener,
+}
+
+impl StepsStateChangeListener for ReentrantListener {
+    fn on_state_change(&self, state: StepsState, version: u64) {
+        self.recording.on_state_change(state.clone(), version);
+        if state.count < self.limit
+            && let Some(model) = self.model.get().and_then(Weak::upgrade)
+        {
+            model.step();
+        }
+    }
+}
+
+#[test]
+fn mutating_from_listener_delivers_in_order() {
+    let listener = Arc::new(ReentrantListener {
+        model: OnceLock::new(),
+        limit: 3,
+        recording: RecordingListener::default(),
+    });
+    let observer = Arc::new(RecordingListener::default());
+    let model = Steps::new(StepsState::default(), listener.clone());
+    let _subscription = model.subscribe(observer.clone());
+    listener.model.set(Arc::downgrade(&model)).unwrap();
+
+    model.step();
+
+    let expected = vec![(1, 1), (2, 2), (3, 3)];
+    assert_eq!(counts(&listener.recording), expected);
+    assert_eq!(counts(&observer), expected);
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use common::UniFfiTag;
+use lera::LeraModel;
+use std::sync::Arc;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct SearchState {
+    pub query: String,
+}
+
+#[lera::model(state = SearchState, notify = debounce(30ms))]
+pub struct Search {}
+
+#[lera::api]
+impl Search {
+    pub fn type_char(&self, c: String) {
+        self.mutate(|state| state.query.push_str(&c));
+    }
+}
+
+#[lera::model(state = SearchState, notify = throttle(40ms))]
+pub struct ThrottledSearch {}
+
+type RecordingListener = common::RecordingListener<SearchState>;
+common::recording_listener!(SearchStateChangeListener, SearchState);
+
+fn queries(listener: &RecordingListener) -> Vec<String> {
+    listener
+        .changes()
+        .into_iter()
+        .map(|state| state.query)
+        .collect()
+}
+
+#[cfg(any(feature = "tokio", feature = "smol"))]
+fn type_text<M: LeraModel<State = SearchState>>(model: &M, text: &str) {
+    for c in text.chars() {
+        model.mutate(|state| state.query.push(c));
+    }
+}
+
+#[cfg(any(feature = "tokio", feature = "smol"))]
+#[test]
+fn debounce_delivers_the_latest_state_once_changes_stop() {
+    let listener = Arc::new(RecordingListener::default());
+    let search = Search::new(SearchState::default(), listener.clone());
+    search.get_tasks().intercept();
+
+    type_text(&*search, "lera");
+    assert_eq!(queries(&listener), Vec::<String>::new());
+    assert_eq!(search.access(|state| state.query), "lera");
+
+    // Each change replaced the delayed delivery of the previous one.
+    assert_eq!(search.get_tasks().intercepted().len(), 1);
+    assert_eq!(search.get_tasks().run_intercepted(), 1);
+    assert_eq!(queries(&listener), vec!["lera"]);
+}
+
+#[cfg(any(feature = "tokio", feature = "smol"))]
+#[test]
+fn throttle_delivers_right_away_then_at_most_once_per_period() {
+    let listener = Arc::new(RecordingListener::default());
+    let search = ThrottledSearch::new(SearchState::default(), listener.clone());
+    search.get_tasks().intercept();
+
+    type_text(&*search, "ler");
+    assert_eq!(queries(&listener), vec!["l"]);
+
+    // Runs the throttling period until no more changes come.
+    assert_eq!(search.get_tasks().run_intercepted(), 1);
+    assert_eq!(queries(&listener), vec!["l", "ler"]);
+
+    // The throttling period is over, the next change is delivered right away.
+    type_text(&*search, "a");
+    assert_eq!(queries(&listener), vec!["l", "ler", "lera"]);
+}
+
+#[test]
+fn flush_delivers_pending_changes_right_away() {
+    let listener = Arc::new(RecordingListener::default());
+    let search = Search::new(SearchState::default(), listener.clone());
+    search.get_tasks().intercept();
+
+    search.type_char("a".to_owned());
+    search.flush();
+    assert_eq!(queries(&listener), vec!["a"]);
+
+    // Nothing is left to deliver after the flush.
+    assert!(search.get_tasks().intercepted().is_empty());
+    assert_eq!(search.get_tasks().run_intercepted(), 0);
+    assert_eq!(queries(&listener), vec!["a"]);
+}
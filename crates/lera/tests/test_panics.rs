@@ -0,0 +1,124 @@
+mod common;
+
+use common::UniFfiTag;
+use lera::{
+    LeraModel,
+    panics::{LeraPanic, PanicHook, install_panic_hook},
+};
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct WalletState {
+    pub balance: u32,
+}
+
+#[lera::model(state = WalletState)]
+pub struct Wallet {}
+
+#[lera::error]
+#[derive(Debug, PartialEq, uniffi::Error)]
+pub enum WalletError {
+    InsufficientFunds,
+}
+
+impl std::fmt::Display for WalletError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+#[lera::api(catch_unwind)]
+impl Wallet {
+    pub fn withdraw(&self, amount: u32) -> Result<u32, WalletError> {
+        if amount == 0 {
+            panic!("withdrawing nothing");
+        }
+        self.mutate(|state| {
+            state.balance = state
+                .balance
+                .checked_sub(amount)
+                .ok_or(WalletError::InsufficientFunds)?;
+            Ok(state.balance)
+        })
+    }
+
+    pub fn deposit(&self, amount: u32) -> Result<(), WalletError> {
+        self.mutate(|state| state.balance += amount);
+        if amount == 0 {
+            panic!("depositing nothing");
+        }
+        Ok(())
+    }
+}
+
+// Methods which cannot return a caught panic are not caught.
+#[lera::api]
+impl Wallet {
+    pub fn balance(&self) -> u32 {
+        self.read(|state| state.balance)
+    }
+}
+
+struct NoopListener;
+
+impl WalletStateChangeListener for NoopListener {
+    fn on_state_change(&self, _state: WalletState, _version: u64) {}
+}
+
+fn wallet(balance: u32) -> Arc<Wallet> {
+    Wallet::new(WalletState { balance }, Arc::new(NoopListener))
+}
+
+#[derive(Default)]
+struct RecordingHook {
+    panics: Mutex<Vec<LeraPanic>>,
+}
+
+impl PanicHook for RecordingHook {
+    fn on_panic(&self, panic: &LeraPanic) {
+        self.panics.lock().unwrap().push(panic.clone());
+    }
+}
+
+#[test]
+fn panics_are_reported_and_returned_as_errors() {
+    let hook = Arc::new(RecordingHook::default());
+    install_panic_hook(hook.clone());
+    let wallet = wallet(10);
+
+    assert_eq!(wallet.withdraw(3), Ok(7));
+    assert_eq!(wallet.withdraw(8), Err(WalletError::InsufficientFunds));
+    assert_eq!(
+        wallet.withdraw(0),
+        Err(WalletError::LeraPanic {
+            method: "withdraw".to_owned(),
+            message: "withdrawing nothing".to_owned(),
+        })
+    );
+
+    assert_eq!(
+        wallet.deposit(0),
+        Err(WalletError::LeraPanic {
+            method: "deposit".to_owned(),
+            message: "depositing nothing".to_owned(),
+        })
+    );
+    assert_eq!(wallet.deposit(5), Ok(()));
+    assert_eq!(wallet.balance(), 12);
+
+    let reported: Vec<String> = hook
+        .panics
+        .lock()
+        .unwrap()
+        .iter()
+        .map(LeraPanic::to_string)
+        .collect();
+    assert_eq!(
+        reported,
+        vec![
+            "'withdraw' panicked: withdrawing nothing",
+            "'deposit' panicked: depositing nothing",
+        ]
+    );
+}
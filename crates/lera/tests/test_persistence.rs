@@ -0,0 +1,90 @@
+mod common;
+
+use common::UniFfiTag;
+use lera::{
+    LeraModel,
+    persistence::{FileStorage, Storage},
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+lera::lera_setup_ffi_for_persistence!();
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[lera::state(persist = "test_persistence_counter")]
+pub struct CounterState {
+    pub count: i64,
+}
+
+#[lera::model(state = CounterState)]
+pub struct Counter {}
+
+#[lera::api]
+impl Counter {
+    pub fn increment(&self) {
+        self.mutate(|state| state.count += 1);
+    }
+
+    pub fn touch(&self) {
+        self.mutate(|_| {});
+    }
+}
+
+/// Stands in for `UserDefaults` / `DataStore`, installed over FFI.
+#[derive(Default)]
+struct InMemoryStorage {
+    values: Mutex<HashMap<String, String>>,
+    saves: Mutex<usize>,
+}
+
+impl StateStorage for InMemoryStorage {
+    fn load(&self, key: String) -> Option<String> {
+        self.values.lock().unwrap().get(&key).cloned()
+    }
+
+    fn save(&self, key: String, value: String) {
+        *self.saves.lock().unwrap() += 1;
+        self.values.lock().unwrap().insert(key, value);
+    }
+}
+
+const KEY: &str = "test_persistence_counter";
+
+/// The storage is global, so a single test uses it from start to end.
+#[test]
+fn state_is_saved_on_change_and_restored_in_new() {
+    let storage = Arc::new(InMemoryStorage::default());
+    install_state_storage(storage.clone());
+
+    let first = Counter::new(CounterState::default(), Arc::new(CounterNoopListener));
+    first.increment();
+    first.increment();
+    first.touch();
+    assert_eq!(*storage.saves.lock().unwrap(), 2);
+    assert_eq!(
+        storage.load(KEY.to_owned()).as_deref(),
+        Some(r#"{"lera.version":1,"state":{"count":2}}"#)
+    );
+
+    let restored = Counter::new(CounterState::default(), Arc::new(CounterNoopListener));
+    assert_eq!(restored.access(|state| state.count), 2);
+
+    storage.save(KEY.to_owned(), "not json".to_owned());
+    let fallback = Counter::new(CounterState { count: 7 }, Arc::new(CounterNoopListener));
+    assert_eq!(fallback.access(|state| state.count), 7);
+}
+
+#[test]
+fn file_storage_round_trips_values() {
+    let directory = std::env::temp_dir().join(format!("lera-test-{}", std::process::id()));
+    let storage = FileStorage::new(&directory);
+
+    assert_eq!(storage.load("missing"), None);
+    storage.save("counter", r#"{"count":1}"#.to_owned());
+    storage.save("counter", r#"{"count":2}"#.to_owned());
+    assert_eq!(storage.load("counter").as_deref(), Some(r#"{"count":2}"#));
+
+    std::fs::remove_dir_all(directory).unwrap();
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use common::UniFfiTag;
+use lera::{
+    LeraModel,
+    persistence::{Storage, install_storage},
+};
+use std::{
+    sync::{Arc, Mutex, OnceLock, Weak},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[lera::state(persist = "test_persistence_saving_counter")]
+pub struct CounterState {
+    pub count: i64,
+}
+
+#[lera::model(state = CounterState)]
+pub struct Counter {}
+
+#[lera::api]
+impl Counter {
+    pub fn increment(&self) {
+        self.mutate(|state| state.count += 1);
+    }
+}
+
+/// Increments the counter from another thread while saving the first state,
+/// which only commits if saving does not hold the lock mutations wait for.
+#[derive(Default)]
+struct MutatingStorage {
+    counter: OnceLock<Weak<Counter>>,
+    incrementing: Mutex<Option<JoinHandle<()>>>,
+    saved: Mutex<Vec<String>>,
+}
+
+impl Storage for MutatingStorage {
+    fn load(&self, _key: &str) -> Option<String> {
+        None
+    }
+
+    fn save(&self, _key: &str, value: String) {
+        let is_first = {
+            let mut saved = self.saved.lock().unwrap();
+            saved.push(value);
+            saved.len() == 1
+        };
+        if !is_first {
+            return;
+        }
+        let counter = self.counter.get().unwrap().upgrade().unwrap();
+        let incrementing = counter.clone();
+        *self.incrementing.lock().unwrap() = Some(thread::spawn(move || incrementing.increment()));
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while counter.read(|state| state.count) < 2 {
+            assert!(Instant::now() < deadline, "saving blocked the mutation");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+}
+
+/// The storage is global, so a single test uses it from start to end.
+#[test]
+fn states_are_saved_without_blocking_mutations_and_the_latest_is_saved_last() {
+    let storage = Arc::new(MutatingStorage::default());
+    install_storage(storage.clone());
+    let counter = Arc::new(Counter::new(
+        CounterState::default(),
+        Arc::new(CounterNoopListener),
+    ));
+    storage.counter.set(Arc::downgrade(&counter)).unwrap();
+
+    counter.increment();
+    let incrementing = storage.incrementing.lock().unwrap().take().unwrap();
+    incrementing.join().unwrap();
+
+    assert_eq!(
+        *storage.saved.lock().unwrap(),
+        [
+            r#"{"lera.version":1,"state":{"count":1}}"#,
+            r#"{"lera.version":1,"state":{"count":2}}"#,
+        ]
+    );
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use common::UniFfiTag;
+use lera::LeraModel;
+use std::sync::{Arc, Mutex};
+
+lera::lera_setup_ffi_for_logging!();
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[lera::state(persist = "test_persistence_without_storage")]
+pub struct DraftState {
+    pub text: String,
+}
+
+#[lera::model(state = DraftState)]
+pub struct Draft {}
+
+#[lera::api]
+impl Draft {
+    pub fn append(&self, text: String) {
+        self.mutate(|state| state.text.push_str(&text));
+    }
+}
+
+/// Keeps the errors logged.
+#[derive(Default)]
+struct ErrorLog {
+    errors: Mutex<Vec<String>>,
+}
+
+impl FfiLogger for ErrorLog {
+    fn log_message(&self, message: String, level: FfiLogLevel) {
+        if matches!(level, FfiLogLevel::Error) {
+            self.errors.lock().unwrap().push(message);
+        }
+    }
+}
+
+// No storage is ever installed in this test binary.
+#[test]
+fn missing_storage_is_reported_once() {
+    let log = Arc::new(ErrorLog::default());
+    install_logger(log.clone());
+
+    let model = Draft::new(DraftState::default(), Arc::new(DraftNoopListener));
+    model.append("a".to_owned());
+    model.append("b".to_owned());
+    let other = Draft::new(DraftState::default(), Arc::new(DraftNoopListener));
+    other.append("c".to_owned());
+
+    assert_eq!(model.access(|state| state.text), "ab");
+    let errors = log.errors.lock().unwrap().clone();
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].contains("No storage installed"));
+}
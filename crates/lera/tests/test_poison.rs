@@ -0,0 +1,106 @@
+mod common;
+
+use common::UniFfiTag;
+use lera::{LeraError, LeraModel};
+use std::{
+    panic::{AssertUnwindSafe, catch_unwind},
+    sync::Arc,
+};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct CartState {
+    pub items: Vec<String>,
+}
+
+#[lera::model(state = CartState)]
+pub struct Cart {}
+
+#[lera::model(state = CartState, poison = reset)]
+pub struct ResettingCart {}
+
+type RecordingListener = common::RecordingListener<CartState>;
+common::recording_listener!(CartStateChangeListener, CartState);
+
+fn cart_state(items: &[&str]) -> CartState {
+    CartState {
+        items: items.iter().map(|item| item.to_string()).collect(),
+    }
+}
+
+/// Adds an item, then panics while still holding the write lock.
+fn panic_while_mutating<M: LeraModel<State = CartState>>(model: &M) {
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        model.mutate(|state| {
+            state.items.push("half added".to_owned());
+            panic!("mutation failed");
+        })
+    }));
+    assert!(result.is_err());
+    assert!(model.get_store().is_poisoned());
+}
+
+#[test]
+fn try_access_and_try_mutate_fail_while_poisoned() {
+    let cart = Cart::new(
+        cart_state(&["apple"]),
+        Arc::new(RecordingListener::default()),
+    );
+    panic_while_mutating(&*cart);
+
+    assert!(matches!(
+        cart.try_access(|state| state.items),
+        Err(LeraError::StatePoisoned { .. })
+    ));
+    assert!(cart.try_mutate(|state| state.items.clear()).is_err());
+}
+
+#[test]
+fn recover_keeps_the_state_before_the_panic() {
+    let cart = Cart::new(
+        cart_state(&["apple"]),
+        Arc::new(RecordingListener::default()),
+    );
+    panic_while_mutating(&*cart);
+
+    // The panicking mutation never committed.
+    assert_eq!(cart.access(|state| state.items), vec!["apple"]);
+    cart.mutate(|state| state.items.push("pear".to_owned()));
+    assert!(!cart.get_store().is_poisoned());
+    assert_eq!(cart.try_access(|state| state.items.len()), Ok(2));
+    assert_eq!(
+        cart.try_mutate(|state| state.items.pop()),
+        Ok(Some("pear".to_owned()))
+    );
+}
+
+#[test]
+fn reset_restores_the_default_state_and_notifies() {
+    let listener = Arc::new(RecordingListener::default());
+    let cart = ResettingCart::new(cart_state(&["apple"]), listener.clone());
+    panic_while_mutating(&*cart);
+
+    cart.mutate(|state| state.items.push("pear".to_owned()));
+    assert_eq!(cart.access(|state| state.items), vec!["pear"]);
+    assert_eq!(
+        listener.changes(),
+        vec![cart_state(&[]), cart_state(&["pear"])]
+    );
+}
+
+#[test]
+fn generated_traits_do_not_panic_while_poisoned() {
+    let cart = Cart::new(
+        cart_state(&["apple"]),
+        Arc::new(RecordingListener::default()),
+    );
+    let other = Cart::new(
+        cart_state(&["apple"]),
+        Arc::new(RecordingListener::default()),
+    );
+    panic_while_mutating(&*cart);
+
+    assert_eq!(*cart, *other);
+    assert_eq!(format!("{:?}", cart), format!("{:?}", other));
+    assert_eq!(cart.to_string(), other.to_string());
+}
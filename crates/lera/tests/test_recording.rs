@@ -0,0 +1,137 @@
+mod common;
+
+use common::UniFfiTag;
+use lera::{LeraModel, recording::Recording};
+use std::sync::Arc;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state(serde)]
+pub struct CartState {
+    pub items: u32,
+}
+
+#[lera::model(state = CartState, recorder)]
+pub struct Cart {}
+
+#[lera::api]
+impl Cart {
+    pub fn add_item(&self) {
+        self.mutate(|state| state.items += 1);
+    }
+
+    pub fn add_two_items(&self) {
+        self.add_item();
+        self.add_item();
+    }
+
+    pub async fn add_item_later(&self) {
+        self.mutate(|state| state.items += 1);
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct WishlistState {
+    pub items: u32,
+}
+
+#[lera::model(state = WishlistState)]
+pub struct Wishlist {}
+
+#[lera::api]
+impl Wishlist {
+    pub fn running_api_method(&self) -> Option<String> {
+        lera::recording::current_api_method().map(str::to_owned)
+    }
+
+    pub async fn running_api_method_later(&self) -> Option<String> {
+        lera::recording::current_api_method().map(str::to_owned)
+    }
+}
+
+type RecordingListener = common::RecordingListener<CartState>;
+common::recording_listener!(CartStateChangeListener, CartState);
+
+fn methods(recording: &Recording<CartState>) -> Vec<Option<&str>> {
+    recording
+        .changes
+        .iter()
+        .map(|change| change.method.as_deref())
+        .collect()
+}
+
+#[test]
+fn records_changes_with_the_api_method_causing_them() {
+    let model = Cart::without_listener(CartState::default());
+    model.add_item();
+    model.start_recording();
+
+    model.add_item();
+    model.add_two_items();
+    model.mutate(|state| state.items = 0);
+    let recording = LeraModel::stop_recording(&model);
+    model.add_item();
+
+    assert_eq!(
+        methods(&recording),
+        vec![
+            Some("add_item"),
+            Some("add_two_items"),
+            Some("add_two_items"),
+            None
+        ]
+    );
+    assert_eq!(recording.initial_state(), Some(&CartState { items: 1 }));
+    assert_eq!(recording.changes[3].before, CartState { items: 4 });
+    assert_eq!(recording.changes[3].after, CartState { items: 0 });
+}
+
+#[cfg(any(feature = "tokio", feature = "smol"))]
+#[test]
+fn async_api_methods_are_recorded() {
+    let model = Cart::without_listener(CartState::default());
+    model.start_recording();
+
+    futures::executor::block_on(model.add_item_later());
+
+    assert_eq!(
+        methods(&LeraModel::stop_recording(&model)),
+        vec![Some("add_item_later")]
+    );
+}
+
+#[test]
+fn exported_recording_replays_onto_a_fresh_model() {
+    let model = Cart::without_listener(CartState { items: 5 });
+    model.start_recording();
+    assert!(model.is_recording());
+    model.add_item();
+    model.add_two_items();
+    let json = model.stop_recording();
+    assert!(!model.is_recording());
+
+    let recording = Recording::<CartState>::from_json(&json).unwrap();
+    let listener = Arc::new(RecordingListener::default());
+    let replayed = Cart::new(recording.initial_state().unwrap().clone(), listener.clone());
+    replayed.replay(&recording);
+
+    assert_eq!(
+        listener.changes(),
+        vec![
+            CartState { items: 6 },
+            CartState { items: 7 },
+            CartState { items: 8 }
+        ]
+    );
+}
+
+#[test]
+fn api_methods_of_models_without_a_recorder_are_not_tracked() {
+    let model = Wishlist::without_listener(WishlistState::default());
+
+    assert_eq!(model.running_api_method(), None);
+    assert_eq!(
+        futures::executor::block_on(model.running_api_method_later()),
+        None
+    );
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use common::UniFfiTag;
+use serde_json::json;
+use std::sync::Arc;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state(serde)]
+pub struct AddressState {
+    pub street_name: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state(serde, persist = "test_serde_person")]
+pub struct PersonState {
+    pub full_name: String,
+    pub home: Option<AddressState>,
+}
+
+#[lera::model(state = PersonState)]
+pub struct Person {}
+
+#[lera::api]
+impl Person {}
+
+#[test]
+fn serde_state_uses_rust_field_names() {
+    let person = PersonState {
+        full_name: "Ada Lovelace".to_owned(),
+        home: Some(AddressState {
+            street_name: "St James's Square".to_owned(),
+        }),
+    };
+
+    let json = serde_json::to_value(&person).unwrap();
+
+    assert_eq!(
+        json,
+        json!({
+            "full_name": "Ada Lovelace",
+            "home": { "street_name": "St James's Square" },
+        })
+    );
+    assert_eq!(serde_json::from_value::<PersonState>(json).unwrap(), person);
+}
@@ -0,0 +1,147 @@
+mod common;
+
+use common::UniFfiTag;
+use lera::LeraModel;
+use std::{
+    sync::{
+        Arc, OnceLock, Weak,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread::spawn,
+};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct LogState {
+    pub lines: Vec<String>,
+}
+
+#[lera::model(state = LogState)]
+pub struct Log {}
+
+type RecordingListener = common::RecordingListener<LogState>;
+common::recording_listener!(LogStateChangeListener, LogState);
+
+fn append(log: &Log, line: &str) {
+    log.mutate(|state| state.lines.push(line.to_owned()));
+}
+
+#[test]
+fn snapshots_are_immutable_and_versioned() {
+    let log = Log::new(LogState::default(), Arc::new(RecordingListener::default()));
+    let initial = log.snapshot();
+
+    append(&log, "started");
+    append(&log, "stopped");
+    let latest = log.snapshot();
+
+    assert_eq!(initial.version(), 0);
+    assert!(initial.lines.is_empty());
+    assert_eq!(latest.version(), 2);
+    assert_eq!(latest.lines, vec!["started", "stopped"]);
+    assert_eq!(latest.version(), log.state_version());
+}
+
+#[test]
+fn read_borrows_the_latest_state() {
+    let log = Log::new(LogState::default(), Arc::new(RecordingListener::default()));
+    append(&log, "started");
+
+    assert_eq!(log.read(|state| state.lines.len()), 1);
+    assert_eq!(log.read(|state| state.lines[0].clone()), "started");
+}
+
+#[test]
+fn mutations_leaving_the_state_equal_are_not_changes() {
+    let listener = Arc::new(RecordingListener::default());
+    let log = Log::new(LogState::default(), listener.clone());
+
+    log.mutate(|_| {});
+
+    assert!(listener.versions().is_empty());
+    assert_eq!(log.snapshot().version(), 0);
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state(skip_compare)]
+pub struct TickState {
+    pub ticks: u64,
+}
+
+#[lera::model(state = TickState)]
+pub struct Ticker {}
+
+struct TickListener;
+
+impl TickStateChangeListener for TickListener {
+    fn on_state_change(&self, _state: TickState, _version: u64) {}
+}
+
+#[test]
+fn every_mutation_is_a_change_when_skipping_the_comparison() {
+    let ticker = Ticker::new(TickState::default(), Arc::new(TickListener));
+
+    ticker.mutate(|_| {});
+
+    assert_eq!(ticker.snapshot().version(), 1);
+    assert_eq!(ticker.state_version(), 1);
+}
+
+/// Checks that the model never has an older state than the one delivered.
+#[derive(Default)]
+struct ReadingListener {
+    log: OnceLock<Weak<Log>>,
+    stale_reads: AtomicUsize,
+}
+
+impl LogStateChangeListener for ReadingListener {
+    fn on_state_change(&self, state: LogState, version: u64) {
+        let Some(log) = self.log.get().and_then(Weak::upgrade) else {
+            return;
+        };
+        let lines = log.access(|state| state.lines.len());
+        if lines < state.lines.len() || log.snapshot().version() < version {
+            self.stale_reads.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+#[test]
+fn listeners_read_at_least_the_state_they_received() {
+    let listener = Arc::new(ReadingListener::default());
+    let log = Log::new(LogState::default(), listener.clone());
+    listener.log.set(Arc::downgrade(&log)).unwrap();
+
+    let writers: Vec<_> = (0..8)
+        .map(|writer| {
+            let log = log.clone();
+            spawn(move || {
+                for line in 0..100 {
+                    append(&log, &format!("{} {}", writer, line));
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    assert_eq!(log.state_version(), 800);
+    assert_eq!(log.snapshot().version(), 800);
+    assert_eq!(listener.stale_reads.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn transactions_read_their_own_mutations() {
+    let log = Log::new(LogState::default(), Arc::new(RecordingListener::default()));
+
+    let lines = log.transaction(|tx| {
+        tx.mutate(|state| state.lines.push("started".to_owned()));
+        // The model itself still has the state before the transaction.
+        assert!(log.read(|state| state.lines.is_empty()));
+        Ok::<_, ()>(tx.read(|state| state.lines.len()))
+    });
+
+    assert_eq!(lines, Ok(1));
+    assert_eq!(log.read(|state| state.lines.len()), 1);
+}
@@ -0,0 +1,177 @@
+mod common;
+
+use common::UniFfiTag;
+use lera::{LeraModel, StateChange};
+use std::sync::Arc;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state(diff)]
+pub struct PlaylistState {
+    pub title: String,
+    pub tracks: Vec<String>,
+    pub playing: Option<String>,
+}
+
+#[lera::model(state = PlaylistState)]
+pub struct Playlist {}
+
+#[lera::api]
+impl Playlist {
+    pub fn rename(self: &Arc<Self>, title: String) {
+        self.mutate(|state| state.title = title);
+    }
+
+    pub fn add_track(self: &Arc<Self>, track: String) {
+        self.mutate(|state| state.tracks.push(track));
+    }
+
+    pub fn play(self: &Arc<Self>, track: Option<String>) {
+        self.mutate(|state| state.playing = track);
+    }
+}
+
+type RecordingListener = common::RecordingListener<PlaylistStateDiff>;
+common::recording_listener!(PlaylistStateChangeListener, PlaylistStateDiff);
+
+#[test]
+fn listeners_receive_only_changed_fields() {
+    let listener = Arc::new(RecordingListener::default());
+    let model = Playlist::new(PlaylistState::default(), listener.clone());
+
+    model.rename("Mix".to_owned());
+    model.add_track("Intro".to_owned());
+    model.rename("Mix".to_owned());
+
+    assert_eq!(
+        listener.changes(),
+        vec![
+            PlaylistStateDiff {
+                title: Some("Mix".to_owned()),
+                tracks: None,
+                playing: None,
+            },
+            PlaylistStateDiff {
+                title: None,
+                tracks: Some(vec!["Intro".to_owned()]),
+                playing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn applying_diffs_reproduces_state() {
+    let listener = Arc::new(RecordingListener::default());
+    let model = Playlist::new(PlaylistState::default(), listener.clone());
+
+    model.add_track("Intro".to_owned());
+    model.rename("Mix".to_owned());
+    model.play(Some("Intro".to_owned()));
+    model.add_track("Outro".to_owned());
+
+    let mut replica = PlaylistState::default();
+    for diff in listener.changes() {
+        diff.apply_to(&mut replica);
+    }
+    assert_eq!(replica, model.access(|state| state));
+}
+
+#[test]
+fn option_fields_always_hold_their_latest_value() {
+    let listener = Arc::new(RecordingListener::default());
+    let model = Playlist::new(PlaylistState::default(), listener.clone());
+
+    model.play(Some("Intro".to_owned()));
+    model.rename("Mix".to_owned());
+    model.play(None);
+
+    assert_eq!(
+        listener.changes(),
+        vec![
+            PlaylistStateDiff {
+                title: None,
+                tracks: None,
+                playing: Some("Intro".to_owned()),
+            },
+            PlaylistStateDiff {
+                title: Some("Mix".to_owned()),
+                tracks: None,
+                playing: Some("Intro".to_owned()),
+            },
+            PlaylistStateDiff {
+                title: None,
+                tracks: None,
+                playing: None,
+            },
+        ]
+    );
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state(diff)]
+pub struct CursorState {
+    pub position: Option<u32>,
+}
+
+#[test]
+fn states_of_only_option_fields_diff_to_their_latest_value() {
+    let moved = CursorState { position: Some(3) };
+    let hidden = CursorState { position: None };
+
+    let diff = CursorState::merge(
+        CursorState::change(&CursorState::default(), &moved),
+        CursorState::change(&moved, &hidden),
+    );
+
+    assert_eq!(diff, CursorStateDiff { position: None });
+    let mut replica = moved;
+    diff.apply_to(&mut replica);
+    assert_eq!(replica, hidden);
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state(diff)]
+pub struct TrackState {
+    pub title: String,
+    pub position: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state(diff)]
+pub struct QueueState {
+    pub length: u32,
+    pub current: Option<TrackState>,
+}
+
+#[test]
+fn option_fields_of_diff_states_hold_their_whole_value() {
+    let intro = TrackState {
+        title: "Intro".to_owned(),
+        position: 0,
+    };
+    let playing = QueueState {
+        length: 1,
+        current: Some(intro.clone()),
+    };
+    let moved = QueueState {
+        current: Some(TrackState {
+            position: 30,
+            ..intro
+        }),
+        ..playing.clone()
+    };
+
+    let diff = QueueState::change(&playing, &moved);
+
+    // Not a `TrackStateDiff` of the position alone.
+    assert_eq!(
+        diff,
+        QueueStateDiff {
+            length: None,
+            current: Some(TrackState {
+                title: "Intro".to_owned(),
+                position: 30,
+            }),
+        }
+    );
+}
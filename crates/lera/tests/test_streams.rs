@@ -0,0 +1,127 @@
+mod common;
+
+use common::UniFfiTag;
+use futures::{StreamExt, executor::block_on, stream};
+use lera::LeraModel;
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    task::Poll,
+    thread::{sleep, spawn},
+    time::Duration,
+};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct DownloadsState {
+    pub catalog: Vec<String>,
+}
+
+#[lera::model(state = DownloadsState)]
+pub struct Downloads {}
+
+#[derive(Debug, PartialEq, Eq, uniffi::Error)]
+pub enum DownloadError {
+    ConnectionLost,
+}
+
+impl std::fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("connection lost")
+    }
+}
+
+/// Sets the flag once dropped, with the stream holding it.
+struct DropFlag(Arc<AtomicBool>);
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+#[lera::api]
+impl Downloads {
+    pub fn search(&self, query: String) -> impl futures::Stream<Item = String> + Send + 'static {
+        let catalog = self.read(|state| state.catalog.clone());
+        stream::iter(catalog).filter(move |title| futures::future::ready(title.contains(&query)))
+    }
+
+    pub fn progress(&self, fail_at: u32) -> lera::Stream<Result<u32, DownloadError>> {
+        lera::Stream::new(stream::iter((0..=100).step_by(50)).map(move |percent| {
+            if percent == fail_at {
+                Err(DownloadError::ConnectionLost)
+            } else {
+                Ok(percent)
+            }
+        }))
+    }
+
+    pub async fn live(&self) -> lera::Stream<u32> {
+        lera::Stream::new(stream::iter([1, 2]).chain(stream::pending()))
+    }
+}
+
+fn downloads() -> Arc<Downloads> {
+    Arc::new(Downloads::without_listener(DownloadsState {
+        catalog: vec!["Cats".to_owned(), "Dogs".to_owned(), "Cats 2".to_owned()],
+    }))
+}
+
+#[test]
+fn exported_streams_yield_items_until_they_end() {
+    let search = downloads().search("Cats".to_owned());
+
+    assert_eq!(block_on(search.next()), Some("Cats".to_owned()));
+    assert_eq!(block_on(search.next()), Some("Cats 2".to_owned()));
+    assert_eq!(block_on(search.next()), None);
+    assert_eq!(block_on(search.next()), None);
+}
+
+#[test]
+fn exported_streams_of_results_throw_their_errors() {
+    let progress = downloads().progress(100);
+
+    assert_eq!(block_on(progress.next()), Ok(Some(0)));
+    assert_eq!(block_on(progress.next()), Ok(Some(50)));
+    assert_eq!(
+        block_on(progress.next()),
+        Err(DownloadError::ConnectionLost)
+    );
+    assert_eq!(block_on(progress.next()), Ok(None));
+}
+
+#[test]
+fn async_methods_can_return_streams() {
+    let live = block_on(downloads().live());
+
+    assert_eq!(block_on(live.next()), Some(1));
+    assert_eq!(block_on(live.next()), Some(2));
+    live.cancel();
+    assert_eq!(block_on(live.next()), None);
+}
+
+#[test]
+fn cancelling_ends_a_pending_next_and_drops_the_stream() {
+    let dropped = Arc::new(AtomicBool::new(false));
+    let flag = DropFlag(dropped.clone());
+    let handle = Arc::new(lera::stream::StreamHandle::new(stream::poll_fn(
+        move |_| {
+            let _ = &flag;
+            Poll::<Option<u32>>::Pending
+        },
+    )));
+
+    let cancelling = handle.clone();
+    let canceller = spawn(move || {
+        sleep(Duration::from_millis(20));
+        cancelling.cancel();
+    });
+
+    assert_eq!(block_on(handle.next()), None);
+    canceller.join().unwrap();
+    assert!(handle.is_cancelled());
+    assert!(dropped.load(Ordering::SeqCst));
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use common::UniFfiTag;
+use lera::LeraModel;
+use std::sync::Arc;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct TallyState {
+    pub count: i64,
+}
+
+#[lera::model(state = TallyState)]
+pub struct Tally {}
+
+#[lera::api]
+impl Tally {
+    pub fn increment(self: &Arc<Self>) {
+        self.mutate(|state| state.count += 1);
+    }
+}
+
+// The constructor, `subscribe` and `state` are exported once per model, not per block.
+#[lera::api]
+impl Tally {
+    pub fn reset(self: &Arc<Self>) {
+        self.mutate(|state| state.count = 0);
+    }
+}
+
+type RecordingListener = common::RecordingListener<TallyState>;
+common::recording_listener!(TallyStateChangeListener, TallyState);
+
+fn counts(listener: &RecordingListener) -> Vec<i64> {
+    listener
+        .changes()
+        .into_iter()
+        .map(|state| state.count)
+        .collect()
+}
+
+#[test]
+fn all_subscribers_are_notified_until_unsubscribed() {
+    let owner = Arc::new(RecordingListener::default());
+    let model = Tally::new(TallyState::default(), owner.clone());
+
+    let first = Arc::new(RecordingListener::default());
+    let second = Arc::new(RecordingListener::default());
+    let first_handle = model.subscribe(first.clone());
+    let second_handle = LeraModel::subscribe(model.as_ref(), second.clone());
+
+    model.increment();
+    first_handle.cancel();
+    assert!(first_handle.is_cancelled());
+    model.increment();
+    drop(second_handle);
+    model.increment();
+
+    assert_eq!(counts(&owner), vec![1, 2, 3]);
+    assert_eq!(counts(&first), vec![1]);
+    assert_eq!(counts(&second), vec![1, 2]);
+    assert_eq!(model.get_notifier().listeners().len(), 1);
+}
+
+#[test]
+fn subscription_does_not_keep_model_alive() {
+    let model = Tally::new(
+        TallyState::default(),
+        Arc::new(RecordingListener::default()),
+    );
+    let handle = model.subscribe(Arc::new(RecordingListener::default()));
+    let weak = Arc::downgrade(&model);
+    drop(model);
+    assert!(weak.upgrade().is_none());
+    assert!(handle.is_cancelled());
+    handle.cancel();
+}
+
+#[test]
+fn models_with_several_api_blocks_export_state_once() {
+    let model = Tally::with_state_and_listener(
+        TallyState { count: 2 },
+        Arc::new(RecordingListener::default()),
+    );
+    model.increment();
+    assert_eq!(model.state(), TallyState { count: 3 });
+    model.reset();
+    assert_eq!(model.state(), TallyState::default());
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use common::{Signal, UniFfiTag};
+use lera::LeraModel;
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct TickerState {
+    pub ticks: u32,
+}
+
+#[lera::model(state = TickerState)]
+pub struct Ticker {}
+
+#[lera::api]
+impl Ticker {
+    pub fn start(self: &Arc<Self>) {
+        self.spawn_interval("ticking", Duration::from_millis(2), |ticker| {
+            ticker.mutate(|state| {
+                state.ticks += 1;
+                state.ticks < 5
+            })
+        });
+    }
+}
+
+/// Raises the signal once the task holding it is dropped.
+struct RaiseOnDrop(Arc<Signal>);
+
+impl Drop for RaiseOnDrop {
+    fn drop(&mut self) {
+        self.0.raise();
+    }
+}
+
+fn ticks(ticker: &Ticker) -> u32 {
+    ticker.access(|state| state.ticks)
+}
+
+#[cfg(any(feature = "tokio", feature = "smol"))]
+#[test]
+fn interval_ticks_until_told_to_stop() {
+    let ticker = Ticker::without_listener(TickerState::default());
+    let ticker = Arc::new(ticker);
+    ticker.get_tasks().intercept();
+    ticker.start();
+    assert!(ticker.is_task_running("ticking"));
+
+    assert_eq!(ticker.get_tasks().run_intercepted(), 1);
+
+    assert_eq!(ticks(&ticker), 5);
+    assert!(!ticker.is_task_running("ticking"));
+}
+
+#[test]
+fn tasks_are_cancelled_when_model_is_dropped() {
+    let ticker = Arc::new(Ticker::without_listener(TickerState::default()));
+    let dropped = Arc::new(Signal::default());
+    let on_drop = RaiseOnDrop(dropped.clone());
+    ticker.spawn(|_| async move {
+        let _on_drop = on_drop;
+        std::future::pending::<()>().await;
+    });
+    let weak = Arc::downgrade(&ticker);
+
+    drop(ticker);
+
+    // Holding the model weakly, the task did not keep it alive.
+    assert!(weak.upgrade().is_none());
+    dropped.wait();
+}
+
+#[test]
+fn spawning_with_same_key_cancels_previous_task() {
+    let ticker = Arc::new(Ticker::without_listener(TickerState::default()));
+    ticker.get_tasks().intercept();
+    let first_finished = Arc::new(AtomicBool::new(false));
+    let flag = first_finished.clone();
+    ticker.spawn_keyed("load", |_| async move {
+        flag.store(true, Ordering::SeqCst);
+    });
+    ticker.spawn_keyed("load", |weak| async move {
+        if let Some(ticker) = weak.upgrade() {
+            ticker.mutate(|state| state.ticks = 42);
+        }
+    });
+
+    assert_eq!(ticker.get_tasks().intercepted(), vec!["load".into()]);
+    assert_eq!(ticker.get_tasks().run_intercepted(), 1);
+
+    assert!(!first_finished.load(Ordering::SeqCst));
+    assert_eq!(ticks(&ticker), 42);
+}
+
+#[test]
+fn replaced_tasks_are_cancelled_before_the_new_one_starts() {
+    let ticker = Arc::new(Ticker::without_listener(TickerState::default()));
+    let (release, released) = futures::channel::oneshot::channel::<()>();
+    let first_finished = Arc::new(AtomicBool::new(false));
+    let first_dropped = Arc::new(Signal::default());
+    let flag = first_finished.clone();
+    let on_drop = RaiseOnDrop(first_dropped.clone());
+    ticker.spawn_keyed("load", |_| async move {
+        let _on_drop = on_drop;
+        let _ = released.await;
+        flag.store(true, Ordering::SeqCst);
+    });
+    ticker.spawn_keyed("load", |_| async move {
+        // Lets the first task finish, were it still running.
+        let _ = release.send(());
+    });
+
+    first_dropped.wait();
+    assert!(!first_finished.load(Ordering::SeqCst));
+}
+
+#[test]
+fn cancel_task_by_key() {
+    let ticker = Arc::new(Ticker::without_listener(TickerState::default()));
+    let key = ticker.spawn(|_| std::future::pending());
+
+    assert!(ticker.cancel_task(key.clone()));
+    assert!(!ticker.cancel_task(key));
+    assert!(!ticker.cancel_task("unknown"));
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use common::UniFfiTag;
+use lera::LeraModel;
+use std::{convert::Infallible, sync::Arc};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state]
+pub struct LedgerState {
+    pub balance: i64,
+    pub entries: u32,
+}
+
+#[lera::model(state = LedgerState)]
+pub struct Ledger {}
+
+#[lera::api]
+impl Ledger {
+    pub fn deposit_twice(self: &Arc<Self>, amount: i64) {
+        let Ok(()) = self.transaction(|tx| {
+            for _ in 0..2 {
+                tx.mutate(|state| {
+                    state.balance += amount;
+                    state.entries += 1;
+                });
+            }
+            Ok::<_, Infallible>(())
+        });
+    }
+
+    pub fn withdraw(self: &Arc<Self>, amount: i64) -> Result<i64, LedgerError> {
+        self.transaction(|tx| {
+            tx.mutate(|state| {
+                state.balance -= amount;
+                state.entries += 1;
+            });
+            let balance = tx.access(|state| state.balance);
+            if balance < 0 {
+                return Err(LedgerError::InsufficientFunds);
+            }
+            Ok(balance)
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, uniffi::Error)]
+pub enum LedgerError {
+    InsufficientFunds,
+}
+
+impl std::fmt::Display for LedgerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("insufficient funds")
+    }
+}
+
+type RecordingListener = common::RecordingListener<LedgerState>;
+common::recording_listener!(LedgerStateChangeListener, LedgerState);
+
+#[test]
+fn committed_transaction_notifies_once() {
+    let listener = Arc::new(RecordingListener::default());
+    let model = Ledger::new(LedgerState::default(), listener.clone());
+
+    model.deposit_twice(5);
+
+    assert_eq!(
+        listener.received(),
+        vec![(
+            1,
+            LedgerState {
+                balance: 10,
+                entries: 2
+            }
+        )]
+    );
+}
+
+#[test]
+fn failed_transaction_rolls_back_without_notifying() {
+    let listener = Arc::new(RecordingListener::default());
+    let model = Ledger::new(LedgerState::default(), listener.clone());
+
+    assert_eq!(model.withdraw(1), Err(LedgerError::InsufficientFunds));
+
+    assert_eq!(model.access(|state| state), LedgerState::default());
+    assert_eq!(model.state_version(), 0);
+    assert!(listener.received().is_empty());
+}
+
+#[test]
+fn unchanged_transaction_does_not_notify() {
+    let listener = Arc::new(RecordingListener::default());
+    let model = Ledger::new(LedgerState::default(), listener.clone());
+
+    let balance = model.transaction(|tx| {
+        tx.mutate(|state| state.balance += 1);
+        tx.mutate(|state| state.balance -= 1);
+        Ok::<_, Infallible>(tx.access(|state| state.balance))
+    });
+
+    assert_eq!(balance, Ok(0));
+    assert!(listener.received().is_empty());
+}
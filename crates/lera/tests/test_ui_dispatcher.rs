@@ -0,0 +1,118 @@
+mod common;
+
+use common::UniFfiTag;
+use lera::LeraModel;
+use std::sync::{Arc, Mutex};
+
+lera::lera_setup_ffi_for_ui_dispatcher!();
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[lera::state(diff)]
+pub struct FormState {
+    pub name: String,
+    pub age: u8,
+}
+
+#[lera::model(state = FormState)]
+pub struct Form {}
+
+#[lera::api]
+impl Form {
+    pub fn set_name(self: &Arc<Self>, name: String) {
+        self.mutate(|state| state.name = name);
+    }
+
+    pub fn set_age(self: &Arc<Self>, age: u8) {
+        self.mutate(|state| state.age = age);
+    }
+}
+
+/// Queues the tasks, the test runs them as the UI thread would.
+#[derive(Default)]
+struct QueueingDispatcher {
+    tasks: Mutex<Vec<Arc<UiTask>>>,
+}
+
+impl QueueingDispatcher {
+    fn run_all(&self) -> usize {
+        let tasks = std::mem::take(&mut *self.tasks.lock().unwrap());
+        tasks.iter().for_each(|task| task.run());
+        tasks.len()
+    }
+
+    /// Drops the tasks without running them, like a UI thread shutting down.
+    fn drop_all(&self) -> usize {
+        std::mem::take(&mut *self.tasks.lock().unwrap()).len()
+    }
+}
+
+impl UiDispatcher for QueueingDispatcher {
+    fn dispatch(&self, task: Arc<UiTask>) {
+        self.tasks.lock().unwrap().push(task);
+    }
+}
+
+type RecordingListener = common::RecordingListener<FormStateDiff>;
+common::recording_listener!(FormStateChangeListener, FormStateDiff);
+
+// The dispatcher is process wide, so everything is tested in one test.
+#[test]
+fn changes_are_delivered_through_dispatcher_and_conflated() {
+    let dispatcher = Arc::new(QueueingDispatcher::default());
+    install_ui_dispatcher(dispatcher.clone());
+
+    let listener = Arc::new(RecordingListener::default());
+    let model = Form::new(FormState::default(), listener.clone());
+
+    model.set_name("A".to_owned());
+    model.set_age(3);
+    model.set_name("Ada".to_owned());
+    assert!(listener.received().is_empty());
+
+    assert_eq!(dispatcher.run_all(), 1);
+    assert_eq!(
+        listener.take(),
+        vec![(
+            3,
+            FormStateDiff {
+                name: Some("Ada".to_owned()),
+                age: Some(3),
+            }
+        )]
+    );
+
+    model.set_age(4);
+    assert_eq!(dispatcher.run_all(), 1);
+    assert_eq!(
+        listener.take(),
+        vec![(
+            4,
+            FormStateDiff {
+                name: None,
+                age: Some(4),
+            }
+        )]
+    );
+
+    // A dropped task does not keep later changes from being dispatched.
+    model.set_age(5);
+    assert_eq!(dispatcher.drop_all(), 1);
+    model.set_name("Ida".to_owned());
+    assert_eq!(dispatcher.run_all(), 1);
+    assert_eq!(
+        listener.take(),
+        vec![(
+            6,
+            FormStateDiff {
+                name: Some("Ida".to_owned()),
+                age: Some(5),
+            }
+        )]
+    );
+
+    // A task outliving its model does nothing.
+    model.set_age(6);
+    drop(model);
+    assert_eq!(dispatcher.run_all(), 1);
+    assert!(listener.received().is_empty());
+}
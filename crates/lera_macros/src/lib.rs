@@ -1,30 +1,15 @@
-use heck::ToSnakeCase;
+use heck::{ToSnakeCase, ToUpperCamelCase};
 use proc_macro::TokenStream;
 use quote::{format_ident, quote};
 use syn::{
     Attribute, Field, Fields, Ident, ImplItem, ImplItemFn, ItemImpl, ItemStruct, Meta, Path, Token,
     Type, parse::Parse, parse::ParseStream, parse_macro_input, punctuated::Punctuated,
+    spanned::Spanned,
 };
 
 #[proc_macro_attribute]
 pub fn state(attr: TokenStream, item: TokenStream) -> TokenStream {
-    // Optional argument: `samples` to also derive samples and export sample constructor.
-    let attr_ts = proc_macro2::TokenStream::from(attr);
-    let mut enable_samples = false;
-    let attr_trimmed = attr_ts.to_string().replace(' ', "");
-    if !attr_trimmed.is_empty() {
-        if attr_trimmed == "samples" {
-            enable_samples = true;
-        } else {
-            return syn::Error::new_spanned(
-                attr_ts,
-                "`#[lera::state]` only supports optional `samples` argument, e.g. #[lera::state(samples)]",
-            )
-            .to_compile_error()
-            .into();
-        }
-    }
-
+    let args = parse_macro_input!(attr as StateArgs);
     let mut item_struct = parse_macro_input!(item as ItemStruct);
 
     let record_path = parse_path("uniffi::Record");
@@ -32,13 +17,26 @@ pub fn state(attr: TokenStream, item: TokenStream) -> TokenStream {
         return err.to_compile_error().into();
     }
 
-    if enable_samples {
+    if args.samples {
         let samples_path = parse_path("samples_derive::Samples");
         if let Err(err) = ensure_derive(&mut item_struct.attrs, &samples_path) {
             return err.to_compile_error().into();
         }
     }
 
+    if args.serde {
+        for derive in ["Serialize", "Deserialize"] {
+            let path = parse_path(&format!("::lera::__private::serde::{}", derive));
+            if let Err(err) = ensure_derive(&mut item_struct.attrs, &path) {
+                return err.to_compile_error().into();
+            }
+        }
+        // Lets the derives find serde through lera, users need not depend on it.
+        item_struct
+            .attrs
+            .push(syn::parse_quote!(#[serde(crate = "::lera::__private::serde")]));
+    }
+
     let struct_ident = item_struct.ident.clone();
     let struct_vis = item_struct.vis.clone();
 
@@ -48,48 +46,584 @@ pub fn state(attr: TokenStream, item: TokenStream) -> TokenStream {
     let fn_name_new_samples =
         format_ident!("new_{}_samples", struct_ident.to_string().to_snake_case());
 
-    let expanded = if enable_samples {
+    // Export a sample-constructor function only when Samples is enabled for this state.
+    let samples_fn = args.samples.then(|| {
         quote! {
-            #item_struct
-
-            #[uniffi::export]
-            #struct_vis fn #fn_name_new_default() -> #struct_ident {
-                #struct_ident::default()
-            }
-
-            // Export a sample-constructor function only when Samples is enabled for this state.
             #[uniffi::export]
             #struct_vis fn #fn_name_new_samples(n: u8) -> Vec<#struct_ident> {
                 use samples_core::Samples;
                 #struct_ident::sample_vec_n(n)
             }
+        }
+    });
 
-            #[uniffi::export(with_foreign)]
-            #struct_vis trait #listener_ident: Send + Sync {
-                fn on_state_change(&self, state: #struct_ident);
+    // With `skip_compare` every mutation counts as a change, sparing a comparison.
+    let has_changed = args.skip_compare.then(|| {
+        quote! {
+            fn has_changed(_old: &Self, _new: &Self) -> bool {
+                true
             }
-
-            ::lera::impl_state_change_listener_bridge!(#listener_ident, #struct_ident);
+        }
+    });
+    let (diff, change) = if args.diff {
+        match state_diff(&item_struct) {
+            Ok(diff) => diff,
+            Err(err) => return err.to_compile_error().into(),
         }
     } else {
-        quote! {
-            #item_struct
+        let change = StateChangeTokens {
+            param: format_ident!("state"),
+            ty: quote!(#struct_ident),
+            change: quote!({
+                let _ = old;
+                new.clone()
+            }),
+            merge: quote!({
+                let _ = older;
+                newer
+            }),
+            apply: quote!({
+                *state = change;
+            }),
+        };
+        (None, change)
+    };
+    let listener_param = change.param.clone();
+    let change_ident = format_ident!("{}Change", struct_ident);
+    let state_change = state_change(&item_struct, change, has_changed);
+    let persistence = match &args.persist {
+        Some(key) => {
+            let version = match &args.version {
+                Some(version) => match version.base10_parse::<u32>() {
+                    Ok(version) => version,
+                    Err(err) => return err.to_compile_error().into(),
+                },
+                None => 1,
+            };
+            // By convention `migrate_v1_to_v2`, `migrate_v2_to_v3`, ... next to the state.
+            let migrations = (1..version).map(|from| {
+                let migration = format_ident!("migrate_v{}_to_v{}", from, from + 1);
+                quote! {
+                    |state| ::lera::persistence::MigrationOutput::into_migrated(#migration(state))
+                }
+            });
+            quote! {
+                impl ::lera::persistence::PersistedState for #struct_ident {
+                    const KEY: &'static str = #key;
+                    const VERSION: u32 = #version;
+                    const MIGRATIONS: &'static [::lera::persistence::Migration] = &[#(#migrations),*];
+                }
 
-            #[uniffi::export]
-            #struct_vis fn #fn_name_new_default() -> #struct_ident {
-                #struct_ident::default()
+                impl ::lera::persistence::Persistence for #struct_ident {
+                    const IS_PERSISTED: bool = true;
+
+                    fn save(&self) {
+                        ::lera::persistence::save(self)
+                    }
+                    fn restore(self) -> Self {
+                        ::lera::persistence::restore(self)
+                    }
+                }
             }
+        }
+        None => quote! {
+            impl ::lera::persistence::Persistence for #struct_ident {}
+        },
+    };
+
+    quote! {
+        #item_struct
+
+        #[uniffi::export]
+        #struct_vis fn #fn_name_new_default() -> #struct_ident {
+            #struct_ident::default()
+        }
+
+        #samples_fn
+
+        #diff
+
+        #state_change
+
+        #persistence
+
+        #[uniffi::export(with_foreign)]
+        #struct_vis trait #listener_ident: Send + Sync {
+            fn on_state_change(&self, #listener_param: #change_ident, version: u64);
+        }
+
+        ::lera::impl_state_change_listener_bridge!(#listener_ident, #struct_ident);
+    }
+    .into()
+}
+
+/// How the change of a state delivered to listeners is made and merged.
+struct StateChangeTokens {
+    /// The name of the listener parameter receiving the change.
+    param: Ident,
+    ty: proc_macro2::TokenStream,
+    /// A block making the change from `old` and `new`.
+    change: proc_macro2::TokenStream,
+    /// A block merging the changes `older` and `newer`.
+    merge: proc_macro2::TokenStream,
+    /// A block applying `change` to `state`.
+    apply: proc_macro2::TokenStream,
+}
+
+/// Generates the `{State}Diff` record of `#[lera::state(diff)]`, holding the new
+/// value of every field that changed, and the change delivering it.
+fn state_diff(
+    item_struct: &ItemStruct,
+) -> syn::Result<(Option<proc_macro2::TokenStream>, StateChangeTokens)> {
+    let struct_ident = &item_struct.ident;
+    let struct_vis = &item_struct.vis;
+    let diff_ident = format_ident!("{}Diff", struct_ident);
+
+    let Fields::Named(fields_named) = &item_struct.fields else {
+        return Err(syn::Error::new_spanned(
+            struct_ident,
+            "`#[lera::state(diff)]` requires a struct with named fields",
+        ));
+    };
+
+    // `Option<Option<T>>` has no faithful Kotlin representation, so `Option`
+    // fields are plain values in the diff, always holding the latest one.
+    let (plain_fields, diffed_fields): (Vec<_>, Vec<_>) = fields_named
+        .named
+        .iter()
+        .partition(|field| type_last_segment_ident(&field.ty).is_ok_and(|ident| ident == "Option"));
+    let plain_idents: Vec<_> = plain_fields.iter().map(|field| &field.ident).collect();
+    let plain_types: Vec<_> = plain_fields.iter().map(|field| &field.ty).collect();
+    let idents: Vec<_> = diffed_fields.iter().map(|field| &field.ident).collect();
+    let types: Vec<_> = diffed_fields.iter().map(|field| &field.ty).collect();
+
+    // Only compared, or merged, when some fields are not `Option`s.
+    let ignore_old = idents.is_empty().then(|| quote!(let _ = old;));
+    let ignore_older = idents.is_empty().then(|| quote!(let _ = older;));
+
+    let doc = format!(
+        "The fields of [`{}`] which changed, `None` for the ones that did not. \
+         `Option` fields always hold their latest value. Changed fields hold \
+         their whole new value, nested values are not diffed, not even the \
+         `Some` value of a `#[lera::state(diff)]` state.",
+        struct_ident
+    );
+
+    let tokens = quote! {
+        #[doc = #doc]
+        #[derive(Clone, Debug, Default, PartialEq, uniffi::Record)]
+        #struct_vis struct #diff_ident {
+            #(pub #idents: Option<#types>,)*
+            #(pub #plain_idents: #plain_types,)*
+        }
 
-            #[uniffi::export(with_foreign)]
-            #struct_vis trait #listener_ident: Send + Sync {
-                fn on_state_change(&self, state: #struct_ident);
+        impl #diff_ident {
+            /// Whether none of the fields changed, `Option` fields not considered.
+            pub fn is_empty(&self) -> bool {
+                true #(&& self.#idents.is_none())*
             }
 
-            ::lera::impl_state_change_listener_bridge!(#listener_ident, #struct_ident);
+            /// Sets the changed fields of `state`.
+            pub fn apply_to(self, state: &mut #struct_ident) {
+                #(
+                    if let Some(value) = self.#idents {
+                        state.#idents = value;
+                    }
+                )*
+                #(state.#plain_idents = self.#plain_idents;)*
+            }
         }
+
+    };
+    let change = StateChangeTokens {
+        param: format_ident!("diff"),
+        ty: quote!(#diff_ident),
+        change: quote!({
+            #ignore_old
+            #diff_ident {
+                #(#idents: (old.#idents != new.#idents).then(|| new.#idents.clone()),)*
+                #(#plain_idents: new.#plain_idents.clone(),)*
+            }
+        }),
+        merge: quote!({
+            #ignore_older
+            #diff_ident {
+                #(#idents: newer.#idents.or(older.#idents),)*
+                #(#plain_idents: newer.#plain_idents,)*
+            }
+        }),
+        apply: quote!({
+            change.apply_to(state);
+        }),
     };
 
-    expanded.into()
+    Ok((Some(tokens), change))
+}
+
+/// Implements `StateChange` delivering `{State}Change`, an alias of the change
+/// made by `change` unless `#[lera::computed]` declares properties of the state.
+///
+/// The alias is glob imported, so that the `{State}Change` record declared by
+/// `#[lera::computed]`, through the `__lera_{state}_computed!` macro generated
+/// here, shadows it. The record then holds the change and the properties of the
+/// new state, evaluated when it is committed.
+fn state_change(
+    item_struct: &ItemStruct,
+    change: StateChangeTokens,
+    has_changed: Option<proc_macro2::TokenStream>,
+) -> proc_macro2::TokenStream {
+    let struct_ident = &item_struct.ident;
+    let struct_vis = &item_struct.vis;
+    let snake_name = struct_ident.to_string().to_snake_case();
+    let change_ident = format_ident!("{}Change", struct_ident);
+    let change_mod = format_ident!("__lera_{}_change", snake_name);
+    let computed_macro = computed_macro_ident(struct_ident);
+    let computed_ident = format_ident!("{}Computed", struct_ident);
+    let computed_mod = computed_mod_ident(struct_ident);
+    let computed_fn_ident = format_ident!("{}_computed", snake_name);
+    let StateChangeTokens {
+        param,
+        ty,
+        change,
+        merge,
+        apply,
+    } = change;
+    let doc = format!(
+        "A change of [`{}`], with the computed properties of the new state.",
+        struct_ident
+    );
+
+    quote! {
+        #[doc(hidden)]
+        mod #change_mod {
+            // Dead when shadowed by the record of `#[lera::computed]`.
+            #[allow(dead_code)]
+            pub type #change_ident = super::#ty;
+        }
+        #[allow(unused_imports)]
+        #struct_vis use #change_mod::*;
+
+        impl ::lera::__private::StateChangeOf<#struct_ident> for #ty {
+            fn change(old: &#struct_ident, new: &#struct_ident) -> Self #change
+            fn merge(older: Self, newer: Self) -> Self #merge
+            fn apply(change: Self, state: &mut #struct_ident) #apply
+        }
+
+        impl ::lera::StateChange for #struct_ident {
+            type Change = #change_ident;
+            fn change(old: &Self, new: &Self) -> #change_ident {
+                <#change_ident as ::lera::__private::StateChangeOf<Self>>::change(old, new)
+            }
+            fn merge(older: #change_ident, newer: #change_ident) -> #change_ident {
+                <#change_ident as ::lera::__private::StateChangeOf<Self>>::merge(older, newer)
+            }
+            fn apply(change: #change_ident, state: &mut Self) {
+                <#change_ident as ::lera::__private::StateChangeOf<Self>>::apply(change, state)
+            }
+            #has_changed
+        }
+
+        #[doc(hidden)]
+        macro_rules! #computed_macro {
+            () => {
+                #[allow(unused_imports)]
+                #struct_vis use #computed_mod::{#computed_fn_ident, #computed_ident};
+
+                #[doc = #doc]
+                #[derive(Clone, Debug, PartialEq, uniffi::Record)]
+                #struct_vis struct #change_ident {
+                    pub #param: #ty,
+                    pub computed: #computed_ident,
+                }
+
+                impl ::lera::__private::StateChangeOf<#struct_ident> for #change_ident {
+                    fn change(old: &#struct_ident, new: &#struct_ident) -> Self {
+                        Self {
+                            #param: <#ty as ::lera::__private::StateChangeOf<#struct_ident>>::change(old, new),
+                            computed: #computed_ident::from(new),
+                        }
+                    }
+                    fn merge(older: Self, newer: Self) -> Self {
+                        Self {
+                            #param: <#ty as ::lera::__private::StateChangeOf<#struct_ident>>::merge(
+                                older.#param,
+                                newer.#param,
+                            ),
+                            computed: newer.computed,
+                        }
+                    }
+                    fn apply(change: Self, state: &mut #struct_ident) {
+                        <#ty as ::lera::__private::StateChangeOf<#struct_ident>>::apply(change.#param, state)
+                    }
+                }
+            };
+        }
+        #[doc(hidden)]
+        #[allow(unused_imports)]
+        pub(crate) use #computed_macro;
+    }
+}
+
+/// The macro `#[lera::state]` generates for `#[lera::computed]` to declare the
+/// `{State}Change` record with, knowing the change and visibility of the state.
+fn computed_macro_ident(state_ident: &Ident) -> Ident {
+    format_ident!(
+        "__lera_{}_computed_change",
+        state_ident.to_string().to_snake_case()
+    )
+}
+
+/// The module `#[lera::computed]` declares the `{State}Computed` record in, for
+/// `#[lera::state]` to bring it into scope with the state's visibility.
+fn computed_mod_ident(state_ident: &Ident) -> Ident {
+    format_ident!(
+        "__lera_{}_computed",
+        state_ident.to_string().to_snake_case()
+    )
+}
+
+/// Marks an impl block of a `#[lera::state]` whose methods are properties
+/// derived from the state, e.g. `fn is_negative(&self) -> bool`.
+///
+/// Generates a `{State}Computed` record holding the value of every method and
+/// an exported `{state}_computed(state)` function evaluating them. The state
+/// evaluates them when committing a change and delivers them with it. Must be
+/// declared in the module of the state.
+#[proc_macro_attribute]
+pub fn computed(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return syn::Error::new_spanned(
+            proc_macro2::TokenStream::from(attr),
+            "`#[lera::computed]` does not accept arguments",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let item_impl = parse_macro_input!(item as ItemImpl);
+    match computed_impl(&item_impl) {
+        Ok(tokens) => quote! {
+            #item_impl
+            #tokens
+        }
+        .into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn computed_impl(item_impl: &ItemImpl) -> syn::Result<proc_macro2::TokenStream> {
+    if item_impl.trait_.is_some() || !item_impl.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            item_impl,
+            "`#[lera::computed]` can only be used on a non-generic inherent impl block",
+        ));
+    }
+    let state_ident = type_last_segment_ident(&item_impl.self_ty)?;
+
+    let mut idents = Vec::new();
+    let mut types = Vec::new();
+    for impl_item in &item_impl.items {
+        let ImplItem::Fn(method) = impl_item else {
+            continue;
+        };
+        let sig = &method.sig;
+        let takes_only_ref_self = sig.inputs.len() == 1
+            && matches!(
+                sig.inputs.first(),
+                Some(syn::FnArg::Receiver(receiver))
+                    if receiver.reference.is_some() && receiver.mutability.is_none()
+            );
+        let syn::ReturnType::Type(_, ty) = &sig.output else {
+            return Err(syn::Error::new_spanned(
+                sig,
+                "computed properties must return a value",
+            ));
+        };
+        if !takes_only_ref_self || sig.asyncness.is_some() || !sig.generics.params.is_empty() {
+            return Err(syn::Error::new_spanned(
+                sig,
+                "computed properties must be `fn name(&self) -> T`",
+            ));
+        }
+        idents.push(sig.ident.clone());
+        types.push(ty.clone());
+    }
+
+    let computed_ident = format_ident!("{}Computed", state_ident);
+    let computed_mod = computed_mod_ident(&state_ident);
+    let computed_macro = computed_macro_ident(&state_ident);
+    let fn_ident = format_ident!("{}_computed", state_ident.to_string().to_snake_case());
+    Ok(quote! {
+        #[doc(hidden)]
+        mod #computed_mod {
+            use super::*;
+
+            /// The computed properties of the state, see `#[lera::computed]`.
+            #[derive(Clone, Debug, PartialEq, uniffi::Record)]
+            pub struct #computed_ident {
+                #(pub #idents: #types,)*
+            }
+
+            impl From<&#state_ident> for #computed_ident {
+                fn from(state: &#state_ident) -> Self {
+                    Self {
+                        #(#idents: state.#idents(),)*
+                    }
+                }
+            }
+
+            #[uniffi::export]
+            pub fn #fn_ident(state: #state_ident) -> #computed_ident {
+                #computed_ident::from(&state)
+            }
+        }
+
+        // Delivers the properties with the changes of the state.
+        #computed_macro!();
+    })
+}
+
+/// Marks an enum of the destinations of a navigation stack, e.g. `Settings` or
+/// `Profile { user_id: String }`.
+///
+/// Generates a `{Enum}Stack` record to hold in the state, with `push`, `pop`
+/// and `pop_to_root`. Pass its field to the model with
+/// `#[lera::model(state = S, navigation = field)]` for the generated ViewModels
+/// to bind it to a `NavigationStack` / `NavHost`.
+#[proc_macro_attribute]
+pub fn navigation(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return syn::Error::new_spanned(
+            proc_macro2::TokenStream::from(attr),
+            "`#[lera::navigation]` does not accept arguments",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let mut item_enum = parse_macro_input!(item as syn::ItemEnum);
+    let enum_path = parse_path("uniffi::Enum");
+    if let Err(err) = ensure_derive(&mut item_enum.attrs, &enum_path) {
+        return err.to_compile_error().into();
+    }
+
+    let enum_ident = &item_enum.ident;
+    let enum_vis = &item_enum.vis;
+    let stack_ident = format_ident!("{}Stack", enum_ident);
+    let doc = format!(
+        "The [`{}`]s navigated to, the root destination not included.",
+        enum_ident
+    );
+
+    quote! {
+        #item_enum
+
+        #[doc = #doc]
+        #[derive(Clone, Debug, Default, PartialEq, Eq, Hash, uniffi::Record)]
+        #enum_vis struct #stack_ident {
+            pub path: Vec<#enum_ident>,
+        }
+
+        impl #stack_ident {
+            pub fn push(&mut self, destination: #enum_ident) {
+                self.path.push(destination);
+            }
+
+            /// Navigates back, returns the destination navigated away from.
+            pub fn pop(&mut self) -> Option<#enum_ident> {
+                self.path.pop()
+            }
+
+            pub fn pop_to_root(&mut self) {
+                self.path.clear();
+            }
+
+            /// Navigates back until `depth` destinations are left.
+            pub fn truncate(&mut self, depth: usize) {
+                self.path.truncate(depth);
+            }
+
+            /// The destination shown, `None` at the root.
+            pub fn top(&self) -> Option<&#enum_ident> {
+                self.path.last()
+            }
+
+            pub fn len(&self) -> usize {
+                self.path.len()
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.path.is_empty()
+            }
+        }
+    }
+    .into()
+}
+
+/// Marks the enum of the actions a model reduces, e.g. `Increment` or
+/// `SetCount { count: i32 }`.
+///
+/// Pass it to the model with `#[lera::model(state = S, actions = MyAction)]`
+/// and implement `lera::Reducer` for the model. The generated ViewModels get
+/// `send(action)` and a method per variant with named or no fields.
+#[proc_macro_attribute]
+pub fn actions(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return syn::Error::new_spanned(
+            proc_macro2::TokenStream::from(attr),
+            "`#[lera::actions]` does not accept arguments",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let mut item_enum = parse_macro_input!(item as syn::ItemEnum);
+    let enum_path = parse_path("uniffi::Enum");
+    if let Err(err) = ensure_derive(&mut item_enum.attrs, &enum_path) {
+        return err.to_compile_error().into();
+    }
+
+    quote!(#item_enum).into()
+}
+
+/// Marks the enum of the one-shot events a model emits to the UI, e.g.
+/// `ReachedTen` or `ShowToast { message: String }`.
+///
+/// Generates the `{Enum}Listener` foreign trait receiving them. Pass it to the
+/// model with `#[lera::model(state = S, events = MyEvent)]` and emit events with
+/// `lera::EventEmitter::emit`. The generated ViewModels get `events`.
+#[proc_macro_attribute]
+pub fn events(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return syn::Error::new_spanned(
+            proc_macro2::TokenStream::from(attr),
+            "`#[lera::events]` does not accept arguments",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let mut item_enum = parse_macro_input!(item as syn::ItemEnum);
+    let enum_path = parse_path("uniffi::Enum");
+    if let Err(err) = ensure_derive(&mut item_enum.attrs, &enum_path) {
+        return err.to_compile_error().into();
+    }
+
+    let enum_ident = &item_enum.ident;
+    let enum_vis = &item_enum.vis;
+    let listener_ident = format_ident!("{}Listener", enum_ident);
+
+    quote! {
+        #item_enum
+
+        #[uniffi::export(with_foreign)]
+        #enum_vis trait #listener_ident: Send + Sync {
+            fn on_event(&self, event: #enum_ident);
+        }
+
+        ::lera::impl_event_listener_bridge!(#listener_ident, #enum_ident);
+    }
+    .into()
 }
 
 #[proc_macro_attribute]
@@ -101,6 +635,26 @@ pub fn default_params(_attr: TokenStream, item: TokenStream) -> TokenStream {
 pub fn model(attr: TokenStream, item: TokenStream) -> TokenStream {
     let args = parse_macro_input!(attr as ModelArgs);
     let state_ty = args.state_ty;
+    let traits = args.traits;
+    let history_capacity = args.history;
+    let enable_recorder = args.recorder;
+    let navigation_field = args.navigation;
+    let notify_policy = args.notify;
+    let poison_policy = args.poison;
+    let actions_ty = args.actions;
+    let events_listener = match &args.events {
+        Some(Type::Path(events_ty)) => events_ty
+            .path
+            .segments
+            .last()
+            .map(|segment| format_ident!("{}Listener", segment.ident)),
+        Some(events_ty) => {
+            return syn::Error::new_spanned(events_ty, "expected the `#[lera::events]` enum")
+                .to_compile_error()
+                .into();
+        }
+        None => None,
+    };
 
     let mut item_struct = parse_macro_input!(item as ItemStruct);
     let object_path = parse_path("uniffi::Object");
@@ -109,28 +663,69 @@ pub fn model(attr: TokenStream, item: TokenStream) -> TokenStream {
     }
 
     let struct_ident = item_struct.ident.clone();
+    let struct_vis = item_struct.vis.clone();
+    let subscription_ident = format_ident!("{}Subscription", struct_ident);
+    let subscription_doc = format!(
+        "Keeps a listener subscribed to the state changes, or events, of [`{}`], \
+         dropping the handle or calling `cancel` detaches it.",
+        struct_ident
+    );
+    let snapshot_ident = format_ident!("{}StateSnapshot", struct_ident);
+    let snapshot_doc = format!(
+        "The state of [`{}`] together with its version, read at once.",
+        struct_ident
+    );
 
     let mut user_fields: Vec<Field> = Vec::new();
+    let mut user_field_options: Vec<FieldOptions> = Vec::new();
     match &mut item_struct.fields {
         Fields::Named(fields_named) => {
             for field in fields_named.named.iter() {
-                user_fields.push(field.clone());
+                let mut field = field.clone();
+                match take_field_options(&mut field.attrs) {
+                    Ok(options) => user_field_options.push(options),
+                    Err(err) => return err.to_compile_error().into(),
+                }
+                user_fields.push(field);
             }
 
             let state_field: Field = syn::parse_quote! {
-                state: Arc<RwLock<#state_ty>>
+                state: ::lera::StateStore<#state_ty>
             };
             let listener_ident = match type_last_segment_ident(&state_ty) {
                 Ok(ident) => format_ident!("{}ChangeListener", ident),
                 Err(err) => return err.to_compile_error().into(),
             };
             let listener_field: Field = syn::parse_quote! {
-                state_change_listener: Arc<dyn #listener_ident>
+                notifier: ::lera::Notifier<Arc<dyn #listener_ident>>
+            };
+
+            let tasks_field: Field = syn::parse_quote! {
+                tasks: ::lera::tasks::Tasks
             };
 
             fields_named.named.clear();
             fields_named.named.push(state_field);
             fields_named.named.push(listener_field);
+            fields_named.named.push(tasks_field);
+            if history_capacity.is_some() {
+                let history_field: Field = syn::parse_quote! {
+                    history: ::lera::History<#state_ty>
+                };
+                fields_named.named.push(history_field);
+            }
+            if enable_recorder {
+                let recorder_field: Field = syn::parse_quote! {
+                    recorder: ::lera::recording::Recorder<#state_ty>
+                };
+                fields_named.named.push(recorder_field);
+            }
+            if let Some(events_listener) = &events_listener {
+                let events_field: Field = syn::parse_quote! {
+                    events: ::lera::events::Events<::std::sync::Arc<dyn #events_listener>>
+                };
+                fields_named.named.push(events_field);
+            }
             for field in user_fields.iter() {
                 fields_named.named.push(field.clone());
             }
@@ -150,90 +745,296 @@ pub fn model(attr: TokenStream, item: TokenStream) -> TokenStream {
         Err(err) => return err.to_compile_error().into(),
     };
 
+    let mut children: Vec<ChildModel> = Vec::new();
+    for (field, options) in user_fields.iter().zip(&user_field_options) {
+        if options.child {
+            match ChildModel::new(&struct_ident, field) {
+                Ok(child) => children.push(child),
+                Err(err) => return err.to_compile_error().into(),
+            }
+        }
+    }
+
     let user_field_inits: Vec<proc_macro2::TokenStream> = user_fields
         .iter()
-        .map(|field| {
+        .zip(&user_field_options)
+        .map(|(field, options)| {
             let ident = field.ident.as_ref().expect("named field must have ident");
-            quote! { #ident: Default::default() }
+            if options.child {
+                quote! { #ident }
+            } else {
+                quote! { #ident: Default::default() }
+            }
         })
         .collect();
 
-    let has_background_task = user_fields.iter().any(|field| {
-        field
-            .ident
-            .as_ref()
-            .map(|name| name == "background_task")
-            .unwrap_or(false)
-    });
-
-    let has_non_eq_field = user_fields.iter().any(|field| {
-        field
-            .ident
-            .as_ref()
-            .map(|name| name == "non_eq")
-            .unwrap_or(false)
-    });
-
-    let has_non_hash_field = user_fields.iter().any(|field| {
-        field
-            .ident
-            .as_ref()
-            .map(|name| name == "non_hash")
-            .unwrap_or(false)
-    });
+    let fields_where = |include: fn(&FieldOptions) -> bool| -> Vec<Field> {
+        user_fields
+            .iter()
+            .zip(&user_field_options)
+            .filter(|(_, options)| include(options))
+            .map(|(field, _)| field.clone())
+            .collect()
+    };
+    // A field skipped from `Eq` must not participate in `Hash`, or equal models could hash differently.
+    // Child models are compared, hashed and formatted through their projection in the state.
+    let eq_fields = fields_where(|options| !options.skip_eq && !options.child);
+    let hash_fields =
+        fields_where(|options| !options.skip_eq && !options.skip_hash && !options.child);
+    let debug_fields = fields_where(|options| !options.skip_debug && !options.child);
 
     let state_ty_clone = state_ty.clone();
 
-    let new_body = if has_background_task {
+    let history_init = history_capacity.map(|capacity| {
+        quote! { history: ::lera::History::new(#capacity), }
+    });
+    let get_history_impl = history_capacity.map(|_| {
         quote! {
-            let should_start_auto_increment = state.is_auto_incrementing;
-            let counter = Arc::new(Self {
-                state: Arc::new(RwLock::new(state)),
-                state_change_listener: listener,
-                #(#user_field_inits,)*
-            });
-            if should_start_auto_increment {
-                counter.start_auto_incrementing();
+            fn get_history(&self) -> Option<&::lera::History<#state_ty>> {
+                Some(&self.history)
             }
-            counter
         }
-    } else {
+    });
+    let recorder_init = enable_recorder.then(|| {
+        quote! { recorder: ::lera::recording::Recorder::default(), }
+    });
+    let events_init = events_listener.as_ref().map(|_| {
+        quote! { events: ::lera::events::Events::default(), }
+    });
+    let get_recorder_impl = enable_recorder.then(|| {
         quote! {
-            Arc::new(Self {
-                state: Arc::new(RwLock::new(state)),
-                state_change_listener: listener,
-                #(#user_field_inits,)*
-            })
+            fn get_recorder(&self) -> Option<&::lera::recording::Recorder<#state_ty>> {
+                Some(&self.recorder)
+            }
         }
-    };
+    });
+    let recorder_exports = enable_recorder.then(|| {
+        quote! {
+            #[uniffi::export]
+            impl #struct_ident {
+                /// Starts recording every state change.
+                pub fn start_recording(&self) {
+                    ::lera::LeraModel::start_recording(self);
+                }
 
-    let mut default_generics = item_struct.generics.clone();
-    if !user_fields.is_empty() {
-        let where_clause = default_generics.make_where_clause();
-        for field in &user_fields {
-            let ty = &field.ty;
-            where_clause
-                .predicates
-                .push(syn::parse_quote! { #ty: Default });
-        }
-    }
-    let (default_impl_generics, default_ty_generics, default_where_clause) =
-        default_generics.split_for_impl();
+                /// Stops recording, returns the recorded state changes as JSON.
+                pub fn stop_recording(&self) -> String {
+                    ::lera::recording::export_json(&::lera::LeraModel::stop_recording(self))
+                }
 
-    let without_listener_generics = item_struct.generics.clone();
-    let (
-        without_listener_impl_generics,
-        without_listener_ty_generics,
-        without_listener_where_clause,
-    ) = without_listener_generics.split_for_impl();
+                pub fn is_recording(&self) -> bool {
+                    ::lera::LeraModel::is_recording(self)
+                }
+            }
 
-    let without_listener_params: Vec<proc_macro2::TokenStream> = user_fields
-        .iter()
-        .map(|field| {
-            let ident = field.ident.as_ref().expect("named field must have ident");
-            let ty = &field.ty;
-            quote! { #ident: #ty }
-        })
+            /// Attributes recorded changes to the `#[lera::api]` methods making
+            /// them, see `lera::__private::ApiMethods`.
+            impl #struct_ident {
+                #[doc(hidden)]
+                fn __lera_enter_api_method(name: &'static str) -> ::lera::recording::ApiMethod {
+                    ::lera::recording::ApiMethod::enter(name)
+                }
+
+                #[doc(hidden)]
+                fn __lera_in_api_method<F: ::std::future::Future>(
+                    name: &'static str,
+                    future: F,
+                ) -> impl ::std::future::Future<Output = F::Output> {
+                    ::lera::recording::in_api_method(name, future)
+                }
+            }
+        }
+    });
+    let get_notify_policy_impl = notify_policy.as_ref().map(|policy| {
+        quote! {
+            fn notify_policy(&self) -> ::lera::NotifyPolicy {
+                #policy
+            }
+        }
+    });
+    let get_poison_policy_impl = poison_policy.as_ref().map(|policy| {
+        quote! {
+            fn poison_policy(&self) -> ::lera::PoisonPolicy {
+                #policy
+            }
+        }
+    });
+    let notify_exports = notify_policy.is_some().then(|| {
+        quote! {
+            #[uniffi::export]
+            impl #struct_ident {
+                /// Notifies listeners of the state changes delayed by `notify` right away.
+                pub fn flush(&self) {
+                    ::lera::LeraModel::flush(self);
+                }
+            }
+        }
+    });
+    let navigation_exports = navigation_field.map(|field| {
+        quote! {
+            #[uniffi::export]
+            impl #struct_ident {
+                /// Called by the generated ViewModels when the user navigated back
+                /// to `depth` destinations, e.g. with the back button.
+                pub fn navigation_did_pop(&self, depth: u32) {
+                    ::lera::LeraModel::mutate(self, |state| state.#field.truncate(depth as usize));
+                }
+            }
+        }
+    });
+    let events_impl = events_listener.map(|events_listener| {
+        quote! {
+            impl ::lera::events::EventEmitter for #struct_ident {
+                type EventListener = ::std::sync::Arc<dyn #events_listener>;
+
+                fn get_events(&self) -> &::lera::events::Events<Self::EventListener> {
+                    &self.events
+                }
+            }
+
+            #[uniffi::export]
+            impl #struct_ident {
+                /// Subscribes `listener` to the events of the model, delivering the
+                /// events emitted while no listener was subscribed first.
+                pub fn subscribe_events(
+                    &self,
+                    listener: ::std::sync::Arc<dyn #events_listener>,
+                ) -> ::std::sync::Arc<#subscription_ident> {
+                    ::std::sync::Arc::new(#subscription_ident(self.events.subscribe(listener)))
+                }
+            }
+        }
+    });
+    let actions_exports = actions_ty.map(|actions_ty| {
+        quote! {
+            #[uniffi::export]
+            impl #struct_ident {
+                /// Called by the generated ViewModels to send `action` to the
+                /// `lera::Reducer` of the model.
+                pub fn send_action(self: ::std::sync::Arc<Self>, action: #actions_ty) {
+                    ::lera::Reducer::send(&self, action);
+                }
+            }
+        }
+    });
+    let history_exports = history_capacity.map(|_| {
+        quote! {
+            #[uniffi::export]
+            impl #struct_ident {
+                /// Restores the state before the latest change.
+                pub fn undo(&self) {
+                    ::lera::LeraModel::undo(self);
+                }
+
+                /// Restores the state most recently undone.
+                pub fn redo(&self) {
+                    ::lera::LeraModel::redo(self);
+                }
+
+                pub fn can_undo(&self) -> bool {
+                    ::lera::LeraModel::can_undo(self)
+                }
+
+                pub fn can_redo(&self) -> bool {
+                    ::lera::LeraModel::can_redo(self)
+                }
+            }
+        }
+    });
+
+    let child_names = children.iter().map(|child| &child.name);
+    let keep_child_projections_impl = (!children.is_empty()).then(|| {
+        quote! {
+            fn keep_child_projections(&self, prev: &Self::State, next: &mut Self::State) {
+                #(next.#child_names.clone_from(&prev.#child_names);)*
+            }
+        }
+    });
+    let child_inits = children.iter().map(ChildModel::init);
+    let child_listeners = children.iter().map(ChildModel::listener);
+    let child_exports = (!children.is_empty()).then(|| {
+        let getters = children.iter().map(ChildModel::getter);
+        quote! {
+            #[uniffi::export]
+            impl #struct_ident {
+                #(#getters)*
+            }
+        }
+    });
+
+    let model_init = quote! {
+        Self {
+            state: ::lera::StateStore::new(state),
+            notifier: ::lera::Notifier::new(listener),
+            tasks: ::lera::tasks::Tasks::default(),
+            #history_init
+            #recorder_init
+            #events_init
+            #(#user_field_inits,)*
+        }
+    };
+    // Children are created with the parent, which they need to notify.
+    let new_model = if children.is_empty() {
+        quote! { Arc::new(#model_init) }
+    } else {
+        quote! {
+            Arc::new_cyclic(|parent: &::std::sync::Weak<Self>| {
+                let mut state = state;
+                #(#child_inits)*
+                #model_init
+            })
+        }
+    };
+
+    let new_body = quote! {
+        let state = ::lera::persistence::Persistence::restore(state);
+        let model = #new_model;
+        {
+            use ::lera::__private::LifecycleHooks as _;
+            Self::__lera_on_init(&model);
+        }
+        model
+    };
+
+    // Calls the `#[lera::on_drop]` hook, declared in any impl block of the model.
+    let (drop_impl_generics, drop_ty_generics, drop_where_clause) =
+        item_struct.generics.split_for_impl();
+    let drop_impl_tokens = quote! {
+        impl #drop_impl_generics Drop for #struct_ident #drop_ty_generics #drop_where_clause {
+            fn drop(&mut self) {
+                use ::lera::__private::LifecycleHooks as _;
+                Self::__lera_on_drop(self);
+            }
+        }
+    };
+
+    let mut default_generics = item_struct.generics.clone();
+    if !user_fields.is_empty() {
+        let where_clause = default_generics.make_where_clause();
+        for field in &user_fields {
+            let ty = &field.ty;
+            where_clause
+                .predicates
+                .push(syn::parse_quote! { #ty: Default });
+        }
+    }
+    let (default_impl_generics, default_ty_generics, default_where_clause) =
+        default_generics.split_for_impl();
+
+    let without_listener_generics = item_struct.generics.clone();
+    let (
+        without_listener_impl_generics,
+        without_listener_ty_generics,
+        without_listener_where_clause,
+    ) = without_listener_generics.split_for_impl();
+
+    let without_listener_params: Vec<proc_macro2::TokenStream> = user_fields
+        .iter()
+        .map(|field| {
+            let ident = field.ident.as_ref().expect("named field must have ident");
+            let ty = &field.ty;
+            quote! { #ident: #ty }
+        })
         .collect();
 
     let without_listener_field_inits: Vec<proc_macro2::TokenStream> = user_fields
@@ -247,11 +1048,15 @@ pub fn model(attr: TokenStream, item: TokenStream) -> TokenStream {
     let without_listener_impl = quote! {
         impl #without_listener_impl_generics #struct_ident #without_listener_ty_generics #without_listener_where_clause {
             pub fn without_listener(state: #state_ty #(, #without_listener_params)*) -> Self {
-                let state_change_listener = Arc::new([<#struct_ident NoopListener>]::default());
+                let listener: Arc<dyn #listener_ident> = Arc::new([<#struct_ident NoopListener>]);
 
                 Self {
-                    state: Arc::new(RwLock::new(state)),
-                    state_change_listener,
+                    state: ::lera::StateStore::new(state),
+                    notifier: ::lera::Notifier::new(listener),
+                    tasks: ::lera::tasks::Tasks::default(),
+                    #history_init
+                    #recorder_init
+                    #events_init
                     #(#without_listener_field_inits,)*
                 }
             }
@@ -274,8 +1079,8 @@ pub fn model(attr: TokenStream, item: TokenStream) -> TokenStream {
         }
     };
 
-    let eq_impl_tokens = if !has_non_eq_field {
-        let eq_checks: Vec<proc_macro2::TokenStream> = user_fields
+    let eq_impl_tokens = if traits.eq {
+        let eq_checks: Vec<proc_macro2::TokenStream> = eq_fields
             .iter()
             .map(|field| {
                 let ident = field
@@ -288,27 +1093,8 @@ pub fn model(attr: TokenStream, item: TokenStream) -> TokenStream {
             .collect();
 
         let state_compare = quote! {
-            {
-                if ::std::sync::Arc::ptr_eq(&self.state, &other.state) {
-                    true
-                } else {
-                    let self_ptr = ::std::sync::Arc::as_ptr(&self.state) as usize;
-                    let other_ptr = ::std::sync::Arc::as_ptr(&other.state) as usize;
-                    if self_ptr < other_ptr {
-                        let self_state = self.state.read().unwrap();
-                        let other_state = other.state.read().unwrap();
-                        *self_state == *other_state
-                    } else if self_ptr > other_ptr {
-                        let other_state = other.state.read().unwrap();
-                        let self_state = self.state.read().unwrap();
-                        *self_state == *other_state
-                    } else {
-                        let self_state = self.state.read().unwrap();
-                        let other_state = other.state.read().unwrap();
-                        *self_state == *other_state
-                    }
-                }
-            }
+            ::core::ptr::eq(&self.state, &other.state)
+                || self.state.read(|state| other.state.read(|other_state| state == other_state))
         };
 
         let mut partial_eq_generics = item_struct.generics.clone();
@@ -317,7 +1103,7 @@ pub fn model(attr: TokenStream, item: TokenStream) -> TokenStream {
             where_clause
                 .predicates
                 .push(syn::parse_quote! { #state_ty: ::core::cmp::PartialEq });
-            for field in &user_fields {
+            for field in &eq_fields {
                 let ty = &field.ty;
                 where_clause
                     .predicates
@@ -333,7 +1119,7 @@ pub fn model(attr: TokenStream, item: TokenStream) -> TokenStream {
             where_clause
                 .predicates
                 .push(syn::parse_quote! { #state_ty: ::core::cmp::Eq });
-            for field in &user_fields {
+            for field in &eq_fields {
                 let ty = &field.ty;
                 where_clause
                     .predicates
@@ -357,8 +1143,8 @@ pub fn model(attr: TokenStream, item: TokenStream) -> TokenStream {
         proc_macro2::TokenStream::new()
     };
 
-    let hash_impl_tokens = if !has_non_hash_field {
-        let hash_statements: Vec<proc_macro2::TokenStream> = user_fields
+    let hash_impl_tokens = if traits.hash {
+        let hash_statements: Vec<proc_macro2::TokenStream> = hash_fields
             .iter()
             .map(|field| {
                 let ident = field
@@ -376,7 +1162,7 @@ pub fn model(attr: TokenStream, item: TokenStream) -> TokenStream {
             where_clause
                 .predicates
                 .push(syn::parse_quote! { #state_ty: ::std::hash::Hash });
-            for field in &user_fields {
+            for field in &hash_fields {
                 let ty = &field.ty;
                 where_clause
                     .predicates
@@ -390,8 +1176,7 @@ pub fn model(attr: TokenStream, item: TokenStream) -> TokenStream {
             impl #hash_impl_generics ::std::hash::Hash for #struct_ident #hash_ty_generics #hash_where_clause {
                 fn hash<H: ::std::hash::Hasher>(&self, state: &mut H) {
                     {
-                        let state_guard = self.state.read().unwrap();
-                        ::std::hash::Hash::hash(&*state_guard, state);
+                        self.state.read(|model_state| ::std::hash::Hash::hash(model_state, state));
                     }
                     #(#hash_statements)*
                 }
@@ -401,63 +1186,92 @@ pub fn model(attr: TokenStream, item: TokenStream) -> TokenStream {
         proc_macro2::TokenStream::new()
     };
 
-    let mut debug_generics = item_struct.generics.clone();
-    {
-        let where_clause = debug_generics.make_where_clause();
-        where_clause
-            .predicates
-            .push(syn::parse_quote! { #state_ty: ::core::fmt::Debug });
-    }
-    let (debug_impl_generics, debug_ty_generics, debug_where_clause) =
-        debug_generics.split_for_impl();
+    let debug_impl_tokens = if traits.debug {
+        let mut debug_generics = item_struct.generics.clone();
+        {
+            let where_clause = debug_generics.make_where_clause();
+            where_clause
+                .predicates
+                .push(syn::parse_quote! { #state_ty: ::core::fmt::Debug });
+            for field in &debug_fields {
+                let ty = &field.ty;
+                where_clause
+                    .predicates
+                    .push(syn::parse_quote! { #ty: ::core::fmt::Debug });
+            }
+        }
+        let (debug_impl_generics, debug_ty_generics, debug_where_clause) =
+            debug_generics.split_for_impl();
 
-    let debug_impl_tokens = quote! {
-        impl #debug_impl_generics ::core::fmt::Debug for #struct_ident #debug_ty_generics #debug_where_clause {
-            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
-                let state = self
-                    .state
-                    .read()
-                    .expect("LeraModel::Debug failed to acquire read lock");
-                ::core::fmt::Debug::fmt(&*state, f)
+        // Without any user fields taking part, the model formats exactly like its state.
+        let debug_body = if debug_fields.is_empty() {
+            quote! { ::core::fmt::Debug::fmt(&*state, f) }
+        } else {
+            let struct_name = struct_ident.to_string();
+            let debug_field_calls: Vec<proc_macro2::TokenStream> = debug_fields
+                .iter()
+                .map(|field| {
+                    let ident = field.ident.as_ref().expect("named field must have ident");
+                    let name = ident.to_string();
+                    quote! { .field(#name, &self.#ident) }
+                })
+                .collect();
+            quote! {
+                f.debug_struct(#struct_name)
+                    .field("state", &*state)
+                    #(#debug_field_calls)*
+                    .finish()
+            }
+        };
+
+        quote! {
+            impl #debug_impl_generics ::core::fmt::Debug for #struct_ident #debug_ty_generics #debug_where_clause {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    let snapshot = self.state.load();
+                    let state = snapshot.state();
+                    #debug_body
+                }
             }
         }
+    } else {
+        proc_macro2::TokenStream::new()
     };
 
-    let mut display_generics = item_struct.generics.clone();
-    {
-        let where_clause = display_generics.make_where_clause();
-        where_clause
-            .predicates
-            .push(syn::parse_quote! { #state_ty: ::core::fmt::Debug });
-    }
-    let (display_impl_generics, display_ty_generics, display_where_clause) =
-        display_generics.split_for_impl();
+    let display_impl_tokens = if traits.display {
+        let mut display_generics = item_struct.generics.clone();
+        {
+            let where_clause = display_generics.make_where_clause();
+            where_clause
+                .predicates
+                .push(syn::parse_quote! { #state_ty: ::core::fmt::Debug });
+        }
+        let (display_impl_generics, display_ty_generics, display_where_clause) =
+            display_generics.split_for_impl();
 
-    let display_impl_tokens = quote! {
-        impl #display_impl_generics ::core::fmt::Display for #struct_ident #display_ty_generics #display_where_clause {
-            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
-                let state = self
-                    .state
-                    .read()
-                    .expect("LeraModel::Display failed to acquire read lock");
-                ::lera::fmt_utils::fmt_model_state(&*state, f)
+        quote! {
+            impl #display_impl_generics ::core::fmt::Display for #struct_ident #display_ty_generics #display_where_clause {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    let snapshot = self.state.load();
+                    let state = snapshot.state();
+                    ::lera::fmt_utils::fmt_model_state(&*state, f)
+                }
             }
         }
+    } else {
+        proc_macro2::TokenStream::new()
     };
 
-    if !has_non_eq_field && !has_non_hash_field {
+    let exported_traits = traits.uniffi_trait_idents();
+    if !exported_traits.is_empty() {
         let export_path = parse_path("uniffi::export");
         let has_export_attr = item_struct
             .attrs
             .iter()
             .any(|attr| attr.path() == &export_path);
         if !has_export_attr {
-            item_struct.attrs.push(syn::parse_quote!(#[uniffi::export(
-                Eq,
-                Hash,
-                Debug,
-                Display
-            )]));
+            item_struct
+                .attrs
+                .push(syn::parse_quote!(#[uniffi::export(#(#exported_traits),*)]));
         }
     }
 
@@ -470,7 +1284,7 @@ pub fn model(attr: TokenStream, item: TokenStream) -> TokenStream {
             struct [<#struct_ident NoopListener>];
 
             impl #listener_ident for [<#struct_ident NoopListener>] {
-                fn on_state_change(&self, _state: #state_ty) {
+                fn on_state_change(&self, _change: <#state_ty as ::lera::StateChange>::Change, _version: u64) {
                     // No-op
                 }
             }
@@ -488,15 +1302,95 @@ pub fn model(attr: TokenStream, item: TokenStream) -> TokenStream {
                 #new_body
             }
 
-            fn get_state_change_listener(&self) -> &Self::Listener {
-                &self.state_change_listener
+            fn get_notifier(&self) -> &::lera::Notifier<Self::Listener> {
+                &self.notifier
             }
 
-            fn get_state_guard(&self) -> &Arc<RwLock<#state_ty_clone>> {
+            fn get_store(&self) -> &::lera::StateStore<#state_ty_clone> {
                 &self.state
             }
+
+            fn get_tasks(&self) -> &::lera::tasks::Tasks {
+                &self.tasks
+            }
+
+            #keep_child_projections_impl
+            #get_history_impl
+            #get_recorder_impl
+            #get_notify_policy_impl
+            #get_poison_policy_impl
+        }
+
+        #[doc = #subscription_doc]
+        #[derive(uniffi::Object)]
+        #struct_vis struct #subscription_ident(::lera::Subscription);
+
+        #[uniffi::export]
+        impl #subscription_ident {
+            pub fn cancel(&self) {
+                self.0.cancel()
+            }
+
+            pub fn is_cancelled(&self) -> bool {
+                self.0.is_cancelled()
+            }
+        }
+
+        #[doc = #snapshot_doc]
+        #[derive(uniffi::Record)]
+        #struct_vis struct #snapshot_ident {
+            pub state: #state_ty,
+            pub version: u64,
+        }
+
+        // Exported once per model, which may have several `#[lera::api]` blocks.
+        #[uniffi::export]
+        impl #struct_ident {
+            #[uniffi::constructor(name = "new")]
+            pub fn with_state_and_listener(
+                state: #state_ty,
+                listener: Arc<dyn #listener_ident>,
+            ) -> Arc<Self> {
+                <Self as ::lera::LeraModel>::new(state, listener)
+            }
+
+            /// Registers another listener of state changes, which stays subscribed
+            /// until the returned handle is cancelled or dropped.
+            pub fn subscribe(
+                &self,
+                listener: Arc<dyn #listener_ident>,
+            ) -> Arc<#subscription_ident> {
+                Arc::new(#subscription_ident(::lera::LeraModel::subscribe(
+                    self, listener,
+                )))
+            }
+
+            /// The latest state, which the ViewModels are seeded with since
+            /// the model may have restored it from storage.
+            pub fn state(&self) -> #state_ty {
+                ::lera::LeraModel::read(self, Clone::clone)
+            }
+
+            /// The latest state and its version, so that a listener subscribed
+            /// before applies only the changes made after it.
+            pub fn state_snapshot(&self) -> #snapshot_ident {
+                let snapshot = ::lera::LeraModel::snapshot(self);
+                #snapshot_ident {
+                    state: snapshot.state().clone(),
+                    version: snapshot.version(),
+                }
+            }
         }
 
+        #(#child_listeners)*
+        #child_exports
+        #navigation_exports
+        #actions_exports
+        #events_impl
+        #notify_exports
+        #history_exports
+        #recorder_exports
+        #drop_impl_tokens
         #eq_impl_tokens
         #hash_impl_tokens
         #debug_impl_tokens
@@ -508,14 +1402,17 @@ pub fn model(attr: TokenStream, item: TokenStream) -> TokenStream {
 
 #[proc_macro_attribute]
 pub fn api(attr: TokenStream, item: TokenStream) -> TokenStream {
-    if !attr.is_empty() {
-        return syn::Error::new_spanned(
-            proc_macro2::TokenStream::from(attr),
-            "`#[lera::api]` does not accept arguments",
-        )
-        .to_compile_error()
-        .into();
-    }
+    let catch_panics = if attr.is_empty() {
+        false
+    } else {
+        let arg = parse_macro_input!(attr as Ident);
+        if arg != "catch_unwind" {
+            return syn::Error::new(arg.span(), "unknown argument, expected `catch_unwind`")
+                .to_compile_error()
+                .into();
+        }
+        true
+    };
 
     let mut item_impl = parse_macro_input!(item as ItemImpl);
     if item_impl.trait_.is_some() {
@@ -527,15 +1424,6 @@ pub fn api(attr: TokenStream, item: TokenStream) -> TokenStream {
         .into();
     }
 
-    // Add `#[uniffi::export]` to the impl block if not already present
-    if !item_impl
-        .attrs
-        .iter()
-        .any(|attr| attr.path().is_ident("uniffi::export"))
-    {
-        item_impl.attrs.push(syn::parse_quote!(#[uniffi::export]));
-    }
-
     let self_ty = item_impl.self_ty.as_ref();
     let struct_ident = match self_ty {
         Type::Path(type_path) => type_path
@@ -555,55 +1443,976 @@ pub fn api(attr: TokenStream, item: TokenStream) -> TokenStream {
         }
     };
 
-    let state_ident = format_ident!("{}State", struct_ident);
-    let listener_ident = format_ident!("{}ChangeListener", state_ident);
+    // Lifecycle hooks are not part of the exported API, move them to a plain impl block.
+    let mut hook_items: Vec<ImplItem> = Vec::new();
+    let mut declared_hooks: Vec<LifecycleHook> = Vec::new();
+    let mut exported_items: Vec<ImplItem> = Vec::new();
+    let mut stream_items: Vec<proc_macro2::TokenStream> = Vec::new();
+    for impl_item in std::mem::take(&mut item_impl.items) {
+        let ImplItem::Fn(mut method) = impl_item else {
+            exported_items.push(impl_item);
+            continue;
+        };
+        let Some(hook) = take_lifecycle_hook_attr(&mut method.attrs) else {
+            if catch_panics && let Err(err) = ensure_returns_result(&method) {
+                return err.to_compile_error().into();
+            }
+            if let Err(err) = run_api_method_effect(&mut method) {
+                return err.to_compile_error().into();
+            }
+            stream_items.extend(export_api_method_stream(&mut method, &struct_ident));
+            record_api_method(&mut method);
+            if method.sig.asyncness.is_some() {
+                poll_in_executor_context(&mut method);
+            }
+            if catch_panics {
+                catch_api_method_panics(&mut method);
+            }
+            exported_items.push(ImplItem::Fn(method));
+            continue;
+        };
+        if declared_hooks.contains(&hook) {
+            return syn::Error::new_spanned(
+                &method.sig,
+                format!(
+                    "only one `#[lera::{}]` method can be declared per model",
+                    hook.attr_name()
+                ),
+            )
+            .to_compile_error()
+            .into();
+        }
+        declared_hooks.push(hook);
+        hook_items.push(hook.dispatch_method(&method.sig.ident));
+        hook_items.push(ImplItem::Fn(method));
+    }
+    item_impl.items = exported_items;
 
-    let has_constructor = item_impl.items.iter().any(|item| match item {
-        ImplItem::Fn(method) => method.sig.ident == "with_state_and_listener",
-        _ => false,
+    let hooks_impl = (!hook_items.is_empty()).then(|| {
+        let (impl_generics, _, where_clause) = item_impl.generics.split_for_impl();
+        let self_ty = &item_impl.self_ty;
+        quote! {
+            impl #impl_generics #self_ty #where_clause {
+                #(#hook_items)*
+            }
+        }
     });
 
-    if !has_constructor {
-        let constructor: ImplItemFn = syn::parse_quote! {
-            #[uniffi::constructor(name = "new")]
-            pub fn with_state_and_listener(
-                state: #state_ident,
-                listener: Arc<dyn #listener_ident>,
-            ) -> Arc<Self> {
-                Self::new(state, listener)
-            }
+    // Add `#[uniffi::export]` to the impl block if not already present
+    if !item_impl
+        .attrs
+        .iter()
+        .any(|attr| attr.path().is_ident("uniffi::export"))
+    {
+        item_impl.attrs.push(syn::parse_quote!(#[uniffi::export]));
+    }
+
+    quote! {
+        #item_impl
+        #hooks_impl
+        #(#stream_items)*
+    }
+    .into()
+}
+
+/// Marks a model method to run right after the model has been constructed by
+/// `LeraModel::new`. The method must take `self: &Arc<Self>` (or `&self`).
+///
+/// Can be used in a `#[lera::api]` impl block or in a plain impl block of the model.
+#[proc_macro_attribute]
+pub fn on_init(attr: TokenStream, item: TokenStream) -> TokenStream {
+    lifecycle_hook(attr, item, LifecycleHook::OnInit)
+}
+
+/// Marks a model method to run when the model is dropped. The method must take `&self`.
+///
+/// Can be used in a `#[lera::api]` impl block or in a plain impl block of the model,
+/// `#[lera::model]` implements `Drop` calling it.
+#[proc_macro_attribute]
+pub fn on_drop(attr: TokenStream, item: TokenStream) -> TokenStream {
+    lifecycle_hook(attr, item, LifecycleHook::OnDrop)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LifecycleHook {
+    OnInit,
+    OnDrop,
+}
+
+impl LifecycleHook {
+    fn attr_name(self) -> &'static str {
+        match self {
+            LifecycleHook::OnInit => "on_init",
+            LifecycleHook::OnDrop => "on_drop",
+        }
+    }
+
+    fn from_attr(attr: &Attribute) -> Option<Self> {
+        let segments = &attr.path().segments;
+        let is_lera_path = match segments.len() {
+            1 => true,
+            2 => segments[0].ident == "lera",
+            _ => false,
         };
-        item_impl.items.insert(0, ImplItem::Fn(constructor));
+        if !is_lera_path {
+            return None;
+        }
+        let last = &segments.last()?.ident;
+        if last == "on_init" {
+            Some(LifecycleHook::OnInit)
+        } else if last == "on_drop" {
+            Some(LifecycleHook::OnDrop)
+        } else {
+            None
+        }
     }
 
-    quote! { #item_impl }.into()
+    /// The hidden inherent method called by the code generated by `#[lera::model]`,
+    /// see `lera::__private::LifecycleHooks`.
+    fn dispatch_method(self, hook_ident: &Ident) -> ImplItem {
+        match self {
+            LifecycleHook::OnInit => syn::parse_quote! {
+                #[doc(hidden)]
+                fn __lera_on_init(self: &::std::sync::Arc<Self>) {
+                    self.#hook_ident();
+                }
+            },
+            LifecycleHook::OnDrop => syn::parse_quote! {
+                #[doc(hidden)]
+                fn __lera_on_drop(&self) {
+                    self.#hook_ident();
+                }
+            },
+        }
+    }
+}
+
+fn take_lifecycle_hook_attr(attrs: &mut Vec<Attribute>) -> Option<LifecycleHook> {
+    let index = attrs
+        .iter()
+        .position(|attr| LifecycleHook::from_attr(attr).is_some())?;
+    LifecycleHook::from_attr(&attrs.remove(index))
+}
+
+fn lifecycle_hook(attr: TokenStream, item: TokenStream, hook: LifecycleHook) -> TokenStream {
+    if !attr.is_empty() {
+        return syn::Error::new_spanned(
+            proc_macro2::TokenStream::from(attr),
+            format!("`#[lera::{}]` does not accept arguments", hook.attr_name()),
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let method = parse_macro_input!(item as ImplItemFn);
+    let dispatch = hook.dispatch_method(&method.sig.ident);
+
+    quote! {
+        #method
+        #dispatch
+    }
+    .into()
+}
+
+/// Arguments of `#[lera::state(samples, diff, serde, skip_compare, persist = "key", version = N)]`.
+#[derive(Default)]
+struct StateArgs {
+    samples: bool,
+    /// Whether listeners receive only the fields which changed, each as a whole
+    /// value: nested structs and `Option` fields are not diffed, even holding
+    /// a `#[lera::state(diff)]` state.
+    diff: bool,
+    serde: bool,
+    /// Whether every mutation is a change, even if it leaves the state equal.
+    skip_compare: bool,
+    persist: Option<syn::LitStr>,
+    version: Option<syn::LitInt>,
+}
+
+impl Parse for StateArgs {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let mut args = Self::default();
+        let metas = Punctuated::<Meta, Token![,]>::parse_terminated(input)?;
+        for meta in metas {
+            let duplicate = |name: &Ident| {
+                syn::Error::new(name.span(), format!("duplicate `{}` argument", name))
+            };
+            let unknown = |span: proc_macro2::Span| {
+                syn::Error::new(
+                    span,
+                    "unknown argument, expected `samples`, `diff`, `serde`, `skip_compare`, `persist = \"key\"` or `version = N`",
+                )
+            };
+            match &meta {
+                Meta::Path(path) => {
+                    let Some(flag) = path.get_ident() else {
+                        return Err(unknown(path.span()));
+                    };
+                    let slot = match flag.to_string().as_str() {
+                        "samples" => &mut args.samples,
+                        "diff" => &mut args.diff,
+                        "serde" => &mut args.serde,
+                        "skip_compare" => &mut args.skip_compare,
+                        _ => return Err(unknown(flag.span())),
+                    };
+                    if *slot {
+                        return Err(duplicate(flag));
+                    }
+                    *slot = true;
+                }
+                Meta::NameValue(name_value) if name_value.path.is_ident("persist") => {
+                    if args.persist.is_some() {
+                        return Err(duplicate(name_value.path.get_ident().unwrap()));
+                    }
+                    let syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(key),
+                        ..
+                    }) = &name_value.value
+                    else {
+                        return Err(syn::Error::new_spanned(
+                            &name_value.value,
+                            "expected a string key, e.g. `persist = \"counter\"`",
+                        ));
+                    };
+                    if key.value().is_empty() {
+                        return Err(syn::Error::new(
+                            key.span(),
+                            "`persist` key must not be empty",
+                        ));
+                    }
+                    args.persist = Some(key.clone());
+                }
+                Meta::NameValue(name_value) if name_value.path.is_ident("version") => {
+                    if args.version.is_some() {
+                        return Err(duplicate(name_value.path.get_ident().unwrap()));
+                    }
+                    let syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Int(version),
+                        ..
+                    }) = &name_value.value
+                    else {
+                        return Err(syn::Error::new_spanned(
+                            &name_value.value,
+                            "expected a version number, e.g. `version = 2`",
+                        ));
+                    };
+                    if version.base10_parse::<u32>()? == 0 {
+                        return Err(syn::Error::new(version.span(), "versions start at 1"));
+                    }
+                    args.version = Some(version.clone());
+                }
+                _ => return Err(unknown(meta.span())),
+            }
+        }
+        if let (Some(version), None) = (&args.version, &args.persist) {
+            return Err(syn::Error::new(
+                version.span(),
+                "`version` requires `persist = \"key\"`",
+            ));
+        }
+        Ok(args)
+    }
+}
+
+/// Runs the `lera::Effect` returned by the method, which then returns nothing
+/// over FFI. Its results mutate the state, see `LeraModel::run_effect`.
+fn run_api_method_effect(method: &mut ImplItemFn) -> syn::Result<()> {
+    let syn::ReturnType::Type(_, ty) = &method.sig.output else {
+        return Ok(());
+    };
+    let is_effect = matches!(
+        &**ty,
+        Type::Path(type_path) if type_path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Effect")
+    );
+    if !is_effect {
+        return Ok(());
+    }
+    if let Some(asyncness) = method.sig.asyncness {
+        return Err(syn::Error::new_spanned(
+            asyncness,
+            "methods returning a `lera::Effect` cannot be async, return `Effect::run(...)` instead",
+        ));
+    }
+    let takes_arc = matches!(
+        method.sig.inputs.first(),
+        Some(syn::FnArg::Receiver(receiver)) if receiver.colon_token.is_some()
+    );
+    if !takes_arc {
+        return Err(syn::Error::new_spanned(
+            &method.sig,
+            "methods returning a `lera::Effect` must take `self: &Arc<Self>` or `self: Arc<Self>`, to run it",
+        ));
+    }
+
+    let block = &method.block;
+    method.block = syn::parse_quote! {{
+        let __lera_model = ::std::sync::Arc::clone(&self);
+        let effect: #ty = (move || #block)();
+        ::lera::LeraModel::run_effect(&__lera_model, effect);
+    }};
+    method.sig.output = syn::ReturnType::Default;
+    Ok(())
+}
+
+/// Exports the stream returned by the method as a `{Model}{Method}Stream` object,
+/// whose async `next` the generated ViewModels iterate, see `lera::stream`.
+fn export_api_method_stream(
+    method: &mut ImplItemFn,
+    model_ident: &Ident,
+) -> Option<proc_macro2::TokenStream> {
+    let syn::ReturnType::Type(_, ty) = &method.sig.output else {
+        return None;
+    };
+    let item_ty = stream_item_type(ty)?.clone();
+    let stream_ident = format_ident!(
+        "{}{}Stream",
+        model_ident,
+        method.sig.ident.to_string().to_upper_camel_case()
+    );
+    let next = match result_type_args(&item_ty) {
+        Some((ok_ty, err_ty)) => quote! {
+            pub async fn next(&self) -> ::std::result::Result<::std::option::Option<#ok_ty>, #err_ty> {
+                self.0.next().await.transpose()
+            }
+        },
+        None => quote! {
+            pub async fn next(&self) -> ::std::option::Option<#item_ty> {
+                self.0.next().await
+            }
+        },
+    };
+    let doc = format!(
+        " The stream returned by `{}::{}`, iterated with `next` until it returns nothing.",
+        model_ident, method.sig.ident
+    );
+
+    let block = &method.block;
+    let stream = if method.sig.asyncness.is_some() {
+        quote!(async move #block.await)
+    } else {
+        quote!((move || #block)())
+    };
+    method.block = syn::parse_quote! {{
+        let stream = #stream;
+        ::std::sync::Arc::new(#stream_ident(::lera::stream::StreamHandle::new(stream)))
+    }};
+    method.sig.output = syn::parse_quote!(-> ::std::sync::Arc<#stream_ident>);
+
+    Some(quote! {
+        #[doc = #doc]
+        #[derive(uniffi::Object)]
+        pub struct #stream_ident(::lera::stream::StreamHandle<#item_ty>);
+
+        #[uniffi::export]
+        impl #stream_ident {
+            #next
+
+            /// Ends the stream, dropping it in Rust.
+            pub fn cancel(&self) {
+                self.0.cancel()
+            }
+        }
+    })
+}
+
+/// The item type of `impl Stream<Item = T>` or `lera::Stream<T>`.
+fn stream_item_type(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::ImplTrait(impl_trait) => impl_trait.bounds.iter().find_map(|bound| {
+            let syn::TypeParamBound::Trait(bound) = bound else {
+                return None;
+            };
+            let segment = bound.path.segments.last()?;
+            if segment.ident != "Stream" {
+                return None;
+            }
+            let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+                return None;
+            };
+            args.args.iter().find_map(|arg| match arg {
+                syn::GenericArgument::AssocType(assoc) if assoc.ident == "Item" => Some(&assoc.ty),
+                _ => None,
+            })
+        }),
+        Type::Path(type_path) => {
+            let segment = type_path.path.segments.last()?;
+            if segment.ident != "Stream" {
+                return None;
+            }
+            match generic_type_args(segment).as_slice() {
+                [item_ty] => Some(item_ty),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The `T` and `E` of `Result<T, E>`.
+fn result_type_args(ty: &Type) -> Option<(&Type, &Type)> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "Result" {
+        return None;
+    }
+    match generic_type_args(segment).as_slice() {
+        [ok_ty, err_ty] => Some((ok_ty, err_ty)),
+        _ => None,
+    }
+}
+
+fn generic_type_args(segment: &syn::PathSegment) -> Vec<&Type> {
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return Vec::new();
+    };
+    args.args
+        .iter()
+        .filter_map(|arg| match arg {
+            syn::GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Attributes the state changes made by the method to it when the model has a
+/// recorder, see `lera::recording` and `lera::__private::ApiMethods`.
+fn record_api_method(method: &mut ImplItemFn) {
+    let name = method.sig.ident.to_string();
+    let block = &method.block;
+    method.block = if method.sig.asyncness.is_some() {
+        syn::parse_quote! {{
+            use ::lera::__private::ApiMethods as _;
+            Self::__lera_in_api_method(#name, async move #block).await
+        }}
+    } else {
+        syn::parse_quote! {{
+            let __lera_api_method = {
+                use ::lera::__private::ApiMethods as _;
+                Self::__lera_enter_api_method(#name)
+            };
+            #block
+        }}
+    };
+}
+
+/// UniFFI polls async methods from the foreign side, make the body run with the
+/// lera executor's context entered, e.g. so that tokio timers work.
+fn poll_in_executor_context(method: &mut ImplItemFn) {
+    let output = match &method.sig.output {
+        syn::ReturnType::Default => quote!(()),
+        syn::ReturnType::Type(_, ty) if matches!(**ty, Type::ImplTrait(_)) => quote!(_),
+        syn::ReturnType::Type(_, ty) => quote!(#ty),
+    };
+    let block = &method.block;
+    method.block = syn::parse_quote! {{
+        ::lera::executor::with_context::<#output, _>(async move #block).await
+    }};
+}
+
+/// Catches a panic in the method, see `lera::panics`.
+fn catch_api_method_panics(method: &mut ImplItemFn) {
+    let name = method.sig.ident.to_string();
+    let output = match &method.sig.output {
+        syn::ReturnType::Default => quote!(()),
+        syn::ReturnType::Type(_, ty) => quote!(#ty),
+    };
+    let block = &method.block;
+    let caught = if method.sig.asyncness.is_some() {
+        quote! { ::lera::panics::catch_async::<#output>(#name, async move #block).await }
+    } else {
+        quote! { ::lera::panics::catch::<#output>(#name, || #block) }
+    };
+    method.block = syn::parse_quote! {{
+        match #caught {
+            Ok(output) => output,
+            Err(panic) => Err(::core::convert::From::from(panic)),
+        }
+    }};
+}
+
+/// Methods of `#[lera::api(catch_unwind)]` return a caught panic as an error,
+/// any other method could only swallow or resume it.
+fn ensure_returns_result(method: &ImplItemFn) -> syn::Result<()> {
+    match &method.sig.output {
+        syn::ReturnType::Type(_, ty) if is_result_type(ty) => Ok(()),
+        output => Err(syn::Error::new(
+            match output {
+                syn::ReturnType::Default => method.sig.ident.span(),
+                syn::ReturnType::Type(_, ty) => ty.span(),
+            },
+            format!(
+                "methods of `#[lera::api(catch_unwind)]` must return `Result<T, E>` to return a caught panic as `Err`, \
+                 declare `{}` in another `#[lera::api]` impl block",
+                method.sig.ident
+            ),
+        )),
+    }
+}
+
+fn is_result_type(ty: &Type) -> bool {
+    matches!(
+        ty,
+        Type::Path(type_path) if type_path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Result")
+    )
+}
+
+/// Adds a `LeraPanic { method, message }` variant to an error enum returned by
+/// the methods of a `#[lera::api(catch_unwind)]` impl block, and the
+/// `From<lera::panics::LeraPanic>` conversion they return it with.
+///
+/// Place it above `#[derive(uniffi::Error)]` so that the derives see the
+/// variant. If other variants declare `#[error(...)]` messages, e.g. with
+/// `thiserror`, the variant gets one too.
+#[proc_macro_attribute]
+pub fn error(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return syn::Error::new_spanned(
+            proc_macro2::TokenStream::from(attr),
+            "`#[lera::error]` does not accept arguments",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let mut item_enum = parse_macro_input!(item as syn::ItemEnum);
+    let has_error_messages = item_enum.variants.iter().any(|variant| {
+        variant
+            .attrs
+            .iter()
+            .any(|attr| attr.path().is_ident("error"))
+    });
+    let message_attr =
+        has_error_messages.then(|| quote!(#[error("'{method}' panicked: {message}")]));
+    item_enum.variants.push(syn::parse_quote! {
+        /// A panic caught in an exported method.
+        #message_attr
+        LeraPanic { method: String, message: String }
+    });
+
+    let (impl_generics, ty_generics, where_clause) = item_enum.generics.split_for_impl();
+    let ident = &item_enum.ident;
+    quote! {
+        #item_enum
+
+        impl #impl_generics ::core::convert::From<::lera::panics::LeraPanic> for #ident #ty_generics #where_clause {
+            fn from(panic: ::lera::panics::LeraPanic) -> Self {
+                Self::LeraPanic {
+                    method: panic.method,
+                    message: panic.message,
+                }
+            }
+        }
+    }
+    .into()
 }
 
 struct ModelArgs {
     state_ty: Type,
+    traits: ModelTraits,
+    /// Number of previous states kept for undo, `history = N`.
+    history: Option<usize>,
+    /// Whether state changes can be recorded, `recorder`.
+    recorder: bool,
+    /// The state field holding a `#[lera::navigation]` stack, `navigation = field`.
+    navigation: Option<Ident>,
+    /// `notify = debounce(16ms)` or `throttle(...)`, a `lera::NotifyPolicy`.
+    notify: Option<proc_macro2::TokenStream>,
+    /// `poison = recover` or `reset`, a `lera::PoisonPolicy`.
+    poison: Option<proc_macro2::TokenStream>,
+    /// The `#[lera::actions]` enum reduced by the model, `actions = MyAction`.
+    actions: Option<Type>,
+    /// The `#[lera::events]` enum emitted by the model, `events = MyEvent`.
+    events: Option<Type>,
 }
 
 impl Parse for ModelArgs {
     fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
-        let key: Ident = input.parse()?;
-        if key != "state" {
+        let mut state_ty: Option<Type> = None;
+        let mut traits: Option<ModelTraits> = None;
+        let mut history: Option<usize> = None;
+        let mut recorder = false;
+        let mut navigation: Option<Ident> = None;
+        let mut notify: Option<proc_macro2::TokenStream> = None;
+        let mut poison: Option<proc_macro2::TokenStream> = None;
+        let mut actions: Option<Type> = None;
+        let mut events: Option<Type> = None;
+
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            match key.to_string().as_str() {
+                "state" if state_ty.is_none() => {
+                    input.parse::<Token![=]>()?;
+                    state_ty = Some(input.parse()?);
+                }
+                "traits" if traits.is_none() => {
+                    let content;
+                    syn::parenthesized!(content in input);
+                    traits = Some(ModelTraits::parse_list(&content)?);
+                }
+                "history" if history.is_none() => {
+                    input.parse::<Token![=]>()?;
+                    let capacity: syn::LitInt = input.parse()?;
+                    let value: usize = capacity.base10_parse()?;
+                    if value == 0 {
+                        return Err(syn::Error::new(
+                            capacity.span(),
+                            "`history` must keep at least one state",
+                        ));
+                    }
+                    history = Some(value);
+                }
+                "recorder" if !recorder => {
+                    recorder = true;
+                }
+                "navigation" if navigation.is_none() => {
+                    input.parse::<Token![=]>()?;
+                    navigation = Some(input.parse()?);
+                }
+                "notify" if notify.is_none() => {
+                    input.parse::<Token![=]>()?;
+                    notify = Some(parse_notify_policy(input)?);
+                }
+                "poison" if poison.is_none() => {
+                    input.parse::<Token![=]>()?;
+                    poison = Some(parse_poison_policy(input)?);
+                }
+                "actions" if actions.is_none() => {
+                    input.parse::<Token![=]>()?;
+                    actions = Some(input.parse()?);
+                }
+                "events" if events.is_none() => {
+                    input.parse::<Token![=]>()?;
+                    events = Some(input.parse()?);
+                }
+                "state" | "traits" | "history" | "recorder" | "navigation" | "notify"
+                | "poison" | "actions" | "events" => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!("duplicate `{}` argument", key),
+                    ));
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "unknown argument, expected `state = MyState`, `traits(...)`, `history = N`, `recorder`, `navigation = field`, `notify = debounce(16ms)`, `poison = reset`, `actions = MyAction` or `events = MyEvent`",
+                    ));
+                }
+            }
+
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+
+        let state_ty = state_ty.ok_or_else(|| {
+            input.error("expected `state` argument, e.g. #[lera::model(state = MyState)]")
+        })?;
+
+        Ok(Self {
+            state_ty,
+            traits: traits.unwrap_or_default(),
+            history,
+            recorder,
+            navigation,
+            notify,
+            poison,
+            actions,
+            events,
+        })
+    }
+}
+
+/// Parses `debounce(16ms)` or `throttle(1s)` into a `lera::NotifyPolicy`.
+fn parse_notify_policy(input: ParseStream<'_>) -> syn::Result<proc_macro2::TokenStream> {
+    let kind: Ident = input.parse()?;
+    let variant = match kind.to_string().as_str() {
+        "debounce" => quote!(Debounce),
+        "throttle" => quote!(Throttle),
+        _ => {
+            return Err(syn::Error::new(
+                kind.span(),
+                "expected `debounce(...)` or `throttle(...)`",
+            ));
+        }
+    };
+    let content;
+    syn::parenthesized!(content in input);
+    let duration: syn::LitInt = content.parse()?;
+    let value: u64 = duration.base10_parse()?;
+    let duration = match duration.suffix() {
+        "ms" => quote!(::std::time::Duration::from_millis(#value)),
+        "s" => quote!(::std::time::Duration::from_secs(#value)),
+        _ => {
             return Err(syn::Error::new(
-                key.span(),
-                "expected `state` argument, e.g. #[lera::model(state = MyState)]",
+                duration.span(),
+                "expected a duration in `ms` or `s`, e.g. `16ms`",
             ));
         }
+    };
+    Ok(quote!(::lera::NotifyPolicy::#variant(#duration)))
+}
 
-        input.parse::<Token![=]>()?;
-        let state_ty: Type = input.parse()?;
+/// Parses `recover` or `reset` into a `lera::PoisonPolicy`.
+fn parse_poison_policy(input: ParseStream<'_>) -> syn::Result<proc_macro2::TokenStream> {
+    let kind: Ident = input.parse()?;
+    match kind.to_string().as_str() {
+        "recover" => Ok(quote!(::lera::PoisonPolicy::Recover)),
+        "reset" => Ok(quote!(::lera::PoisonPolicy::Reset)),
+        _ => Err(syn::Error::new(
+            kind.span(),
+            "expected `recover` or `reset`",
+        )),
+    }
+}
 
-        if input.peek(Token![,]) {
-            input.parse::<Token![,]>()?;
-            if !input.is_empty() {
-                return Err(input.error("unexpected additional arguments"));
+/// Which traits `#[lera::model]` implements for the model and exports over FFI,
+/// controlled by `#[lera::model(state = S, traits(eq, hash, debug, display))]`.
+#[derive(Clone, Copy)]
+struct ModelTraits {
+    eq: bool,
+    hash: bool,
+    debug: bool,
+    display: bool,
+}
+
+impl Default for ModelTraits {
+    fn default() -> Self {
+        Self {
+            eq: true,
+            hash: true,
+            debug: true,
+            display: true,
+        }
+    }
+}
+
+impl ModelTraits {
+    fn parse_list(input: ParseStream<'_>) -> syn::Result<Self> {
+        let mut traits = Self {
+            eq: false,
+            hash: false,
+            debug: false,
+            display: false,
+        };
+        let idents = Punctuated::<Ident, Token![,]>::parse_terminated(input)?;
+        for ident in &idents {
+            let flag = match ident.to_string().as_str() {
+                "eq" => &mut traits.eq,
+                "hash" => &mut traits.hash,
+                "debug" => &mut traits.debug,
+                "display" => &mut traits.display,
+                _ => {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "unknown trait, expected one of `eq`, `hash`, `debug` or `display`",
+                    ));
+                }
+            };
+            *flag = true;
+        }
+        if traits.hash && !traits.eq {
+            return Err(syn::Error::new_spanned(
+                idents,
+                "`hash` requires `eq`, since foreign languages require hashable types to be equatable",
+            ));
+        }
+        Ok(traits)
+    }
+
+    fn uniffi_trait_idents(&self) -> Vec<Ident> {
+        [
+            (self.eq, "Eq"),
+            (self.hash, "Hash"),
+            (self.debug, "Debug"),
+            (self.display, "Display"),
+        ]
+        .into_iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, name)| format_ident!("{}", name))
+        .collect()
+    }
+}
+
+/// Options set on a model field with `#[lera(...)]`.
+#[derive(Default)]
+struct FieldOptions {
+    skip_eq: bool,
+    skip_hash: bool,
+    skip_debug: bool,
+    /// `#[lera::child]`, the field holds a child model.
+    child: bool,
+}
+
+/// Removes all `#[lera(...)]` and `#[lera::child]` attributes from a model field
+/// and returns the options they set.
+fn take_field_options(attrs: &mut Vec<Attribute>) -> syn::Result<FieldOptions> {
+    let mut options = FieldOptions::default();
+    let mut result = Ok(());
+    attrs.retain(|attr| {
+        if attr.path() == &parse_path("lera::child") {
+            match attr.meta.require_path_only() {
+                Ok(_) => options.child = true,
+                Err(err) => result = Err(err),
+            }
+            return false;
+        }
+        if !attr.path().is_ident("lera") {
+            return true;
+        }
+        let parsed = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip_eq") {
+                options.skip_eq = true;
+            } else if meta.path.is_ident("skip_hash") {
+                options.skip_hash = true;
+            } else if meta.path.is_ident("skip_debug") {
+                options.skip_debug = true;
+            } else {
+                return Err(meta.error(
+                    "unknown field option, expected one of `skip_eq`, `skip_hash` or `skip_debug`",
+                ));
+            }
+            Ok(())
+        });
+        if let Err(err) = parsed {
+            result = Err(err);
+        }
+        false
+    });
+    result.map(|_| options)
+}
+
+/// A `#[lera::child]` field of a model, `name: Arc<Child>`, whose state is
+/// projected into the field of the same name of the parent state.
+struct ChildModel {
+    name: Ident,
+    model_ty: Type,
+    /// Notifies the parent of the child's state changes.
+    listener_ident: Ident,
+    /// The `{State}ChangeListener` trait of the child.
+    child_listener_trait: Path,
+    parent_ident: Ident,
+}
+
+impl ChildModel {
+    fn new(parent_ident: &Ident, field: &Field) -> syn::Result<Self> {
+        let name = field.ident.clone().expect("named field must have ident");
+        let model_ty = arc_inner_type(&field.ty).ok_or_else(|| {
+            syn::Error::new_spanned(&field.ty, "`#[lera::child]` fields must be `Arc<Model>`")
+        })?;
+        // Same convention as `#[lera::api]`, the state of model `Foo` is `FooState`.
+        let Type::Path(child_path) = &model_ty else {
+            return Err(syn::Error::new_spanned(
+                &model_ty,
+                "Unsupported child model type for `#[lera::child]`",
+            ));
+        };
+        let mut child_listener_trait = child_path.path.clone();
+        let last = child_listener_trait
+            .segments
+            .last_mut()
+            .expect("type path has a segment");
+        last.ident = format_ident!("{}StateChangeListener", last.ident);
+        last.arguments = syn::PathArguments::None;
+        Ok(Self {
+            listener_ident: format_ident!(
+                "{}{}ChildListener",
+                parent_ident,
+                name.to_string().to_upper_camel_case()
+            ),
+            name,
+            model_ty,
+            child_listener_trait,
+            parent_ident: parent_ident.clone(),
+        })
+    }
+
+    /// Creates the child in `LeraModel::new` of the parent, before the parent
+    /// exists, so it gets the parent weakly.
+    fn init(&self) -> proc_macro2::TokenStream {
+        let Self {
+            name,
+            model_ty,
+            listener_ident,
+            ..
+        } = self;
+        quote! {
+            let #name = <#model_ty as ::lera::LeraModel>::new(
+                state.#name.clone(),
+                Arc::new(#listener_ident { parent: parent.clone() }),
+            );
+            // The child might have restored a persisted state of its own.
+            state.#name = ::lera::LeraModel::access(&*#name, |state| state);
+        }
+    }
+
+    fn listener(&self) -> proc_macro2::TokenStream {
+        let Self {
+            name,
+            model_ty,
+            listener_ident,
+            child_listener_trait,
+            parent_ident,
+        } = self;
+        quote! {
+            struct #listener_ident {
+                parent: ::std::sync::Weak<#parent_ident>,
+            }
+
+            impl #child_listener_trait for #listener_ident {
+                fn on_state_change(
+                    &self,
+                    change: <<#model_ty as ::lera::LeraModel>::State as ::lera::StateChange>::Change,
+                    _version: u64,
+                ) {
+                    let Some(parent) = self.parent.upgrade() else {
+                        return;
+                    };
+                    // Delivered in version order, so the projection follows the child.
+                    ::lera::__private::project_child(&*parent, |state| {
+                        ::lera::StateChange::apply(change, &mut state.#name)
+                    });
+                }
             }
         }
+    }
+
+    fn getter(&self) -> proc_macro2::TokenStream {
+        let Self { name, model_ty, .. } = self;
+        quote! {
+            /// The child model, shared with the parent.
+            pub fn #name(&self) -> Arc<#model_ty> {
+                self.#name.clone()
+            }
+        }
+    }
+}
 
-        Ok(Self { state_ty })
+/// `T` of `Arc<T>`.
+fn arc_inner_type(ty: &Type) -> Option<Type> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "Arc" {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        syn::GenericArgument::Type(inner) if args.args.len() == 1 => Some(inner.clone()),
+        _ => None,
     }
 }
 
@@ -635,10 +2444,10 @@ fn ensure_derive(attrs: &mut Vec<Attribute>, derive_to_add: &Path) -> syn::Resul
 }
 
 fn type_last_segment_ident(ty: &Type) -> syn::Result<Ident> {
-    if let Type::Path(type_path) = ty {
-        if let Some(segment) = type_path.path.segments.last() {
-            return Ok(segment.ident.clone());
-        }
+    if let Type::Path(type_path) = ty
+        && let Some(segment) = type_path.path.segments.last()
+    {
+        return Ok(segment.ident.clone());
     }
     Err(syn::Error::new_spanned(
         ty,
@@ -119,13 +119,10 @@ pub fn build_android(
                     .ok_or("Failed to derive artifact file name")?,
             );
             fs::copy(&artifact, &dest).map_err(|error| {
-                std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    format!(
-                        "Failed to copy {:?} to {:?}. Ensure Android NDK toolchain is installed and cargo target configuration exists. {}",
-                        artifact, dest, error
-                    ),
-                )
+                std::io::Error::other(format!(
+                    "Failed to copy {:?} to {:?}. Ensure Android NDK toolchain is installed and cargo target configuration exists. {}",
+                    artifact, dest, error
+                ))
             })?;
             jni_lib_paths.push(dest);
         }
@@ -11,6 +11,8 @@ internal object TestEnvironment {
     init {
         configureNativeLibrary()
         installTimber()
+        // JVM unit tests have no `Dispatchers.Main`, deliver state changes synchronously.
+        ImmediateUiDispatcher.install()
     }
 
     fun ensure() = Unit
@@ -11,6 +11,10 @@ import XCTest
 
 @Suite("CounterViewModelTests")
 struct CounterViewModelTests {
+	init() {
+		// Delivers state changes synchronously, instead of later on the main actor.
+		ImmediateUiDispatcher.install()
+	}
 	
 	@Test("state stamples", arguments: 1 ... 10)
 	func stateSamples(n: UInt8) {
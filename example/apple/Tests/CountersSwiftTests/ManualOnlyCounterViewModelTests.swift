@@ -11,6 +11,11 @@ import Testing
 
 @Suite("ManualOnlyCounterViewModelTests")
 struct ManualOnlyCounterViewModelTests {
+	init() {
+		// Delivers state changes synchronously, instead of later on the main actor.
+		ImmediateUiDispatcher.install()
+	}
+
 	@Test("tellFullName with parameters")
 	func testTellFullName() async throws {
 		let counter = ManualOnlyCounterViewModel()
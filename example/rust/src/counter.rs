@@ -1,8 +1,8 @@
-use crate::background_task::BackgroundTask;
 use crate::prelude::*;
 use lera::LeraModel;
 use std::{
-    sync::{Arc, RwLock},
+    convert::Infallible,
+    sync::Arc,
     time::Duration,
 };
 
@@ -58,15 +58,23 @@ impl Default for CounterState {
     }
 }
 
+/// Key of the task auto incrementing the counter.
+const AUTO_INCREMENT: &str = "auto_increment";
+
 #[lera::model(state = CounterState)]
-pub struct Counter {
-    background_task: BackgroundTask,
-}
+pub struct Counter {}
 
 impl Counter {
+    #[lera::on_init]
+    fn resume_auto_incrementing(self: &Arc<Self>) {
+        if self.access(|state| state.is_auto_incrementing) {
+            self.start_auto_incrementing();
+        }
+    }
+
     fn do_stop_auto_incrementing(&self) {
         debug!("Stopping auto incrementing");
-        self.background_task.stop();
+        self.cancel_task(AUTO_INCREMENT);
     }
 
     fn increment(self: &Arc<Self>) {
@@ -78,7 +86,7 @@ impl Counter {
 
     fn start_auto_incrementing(self: &Arc<Self>) {
         debug!("Request to start auto incrementing");
-        if self.background_task.is_running() {
+        if self.is_task_running(AUTO_INCREMENT) {
             warn!("Auto-increment task is already running, not starting another");
             return;
         }
@@ -89,34 +97,21 @@ impl Counter {
             state.is_auto_incrementing = true;
         });
 
-        // Create a weak reference to self for the background task
-        let weak_self = Arc::downgrade(self);
         debug!(
             "Starting auto-increment background task with interval {:?}",
             interval_ms
         );
-        self.background_task.start(interval_ms, move || {
-            if let Some(strong_self) = weak_self.upgrade() {
-                // Call the existing increment method - no code duplication!
-                strong_self.increment();
+        // Holds the counter weakly and is cancelled when it is dropped.
+        self.spawn_interval(AUTO_INCREMENT, interval_ms, |counter| {
+            // Call the existing increment method - no code duplication!
+            counter.increment();
 
-                // Check if we should continue
-                strong_self.access(|state| state.is_auto_incrementing)
-            } else {
-                info!("Counter instance has been dropped, stopping auto-increment task");
-                false // Counter was dropped, stop the task
-            }
+            // Check if we should continue
+            counter.access(|state| state.is_auto_incrementing)
         });
     }
 }
 
-impl Drop for Counter {
-    fn drop(&mut self) {
-        info!("Dropping Counter instance, stopping any background task");
-        self.do_stop_auto_incrementing();
-    }
-}
-
 // Exported API
 #[lera::api]
 impl Counter {
@@ -141,10 +136,13 @@ impl Counter {
     }
 
     pub fn stop_auto_incrementing_button_tapped(self: &Arc<Self>) {
-        self.mutate(|state| {
-            state.is_auto_incrementing = false;
+        let Ok(()) = self.transaction(|tx| {
+            tx.mutate(|state| {
+                state.is_auto_incrementing = false;
+            });
+            self.do_stop_auto_incrementing();
+            Ok::<_, Infallible>(())
         });
-        self.do_stop_auto_incrementing();
     }
 }
 
@@ -159,8 +157,8 @@ mod tests {
             is_auto_incrementing: false,
             auto_increment_interval_ms: Interval::try_from(1).unwrap(),
         };
-        let a = Counter::without_listener(state.clone(), BackgroundTask::default());
-        let b = Counter::without_listener(state, BackgroundTask::default());
+        let a = Counter::without_listener(state.clone());
+        let b = Counter::without_listener(state);
         assert_eq!(a, b);
     }
 
@@ -172,9 +170,9 @@ mod tests {
             auto_increment_interval_ms: Interval::try_from(5).unwrap(),
         };
         let listener: Arc<dyn CounterStateChangeListener> =
-            Arc::new(super::CounterNoopListener::default());
+            Arc::new(super::CounterNoopListener);
         let other_listener: Arc<dyn CounterStateChangeListener> =
-            Arc::new(super::CounterNoopListener::default());
+            Arc::new(super::CounterNoopListener);
         let a = Counter::new(state.clone(), listener);
         let b = Counter::new(state, other_listener);
         assert_eq!(a, b);
@@ -182,21 +180,18 @@ mod tests {
 
     #[test]
     fn debug_formats_state() {
-        let counter = Counter::without_listener(CounterState::default(), BackgroundTask::default());
+        let counter = Counter::without_listener(CounterState::default());
         let output = format!("{:?}", counter);
         assert!(output.contains("CounterState"));
     }
 
     #[test]
     fn display_formats_state() {
-        let counter = Counter::without_listener(
-            CounterState {
-                count: 42,
-                is_auto_incrementing: false,
-                auto_increment_interval_ms: Interval::try_from(100).unwrap(),
-            },
-            BackgroundTask::default(),
-        );
+        let counter = Counter::without_listener(CounterState {
+            count: 42,
+            is_auto_incrementing: false,
+            auto_increment_interval_ms: Interval::try_from(100).unwrap(),
+        });
         let output = format!("{}", counter);
         assert!(output.contains("42"));
     }
@@ -261,7 +256,7 @@ mod tests {
         assert_eq!(count, 8);
         let model_samples: Vec<Counter> = state_samples
             .into_iter()
-            .map(|state| Counter::without_listener(state, BackgroundTask::default()))
+            .map(Counter::without_listener)
             .collect();
         assert_eq!(model_samples.len(), 8);
     }
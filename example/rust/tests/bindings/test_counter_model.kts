@@ -8,7 +8,7 @@ class SimpleListener(initialState: CounterState) : CounterStateChangeListener {
     @Volatile
     var state: CounterState = initialState
 
-    override fun onStateChange(state: CounterState) {
+    override fun onStateChange(state: CounterState, version: ULong) {
         this.state = state
     }
 }
@@ -16,7 +16,7 @@
             self.state = state
         }
 
-        func onStateChange(state: CounterState) {
+        func onStateChange(state: CounterState, version: UInt64) {
             self.state = state
         }
 